@@ -0,0 +1,13 @@
+//! The applications every target ships with.  Add new applications here,
+//! not in the individual targets.
+
+#![no_std]
+
+shared::registry! {
+    pub Applications {
+        "Clock" => clock::Clock::new(),
+        "Hardware Test" => hardware_test::HardwareTest::default(),
+        "Keyboard" => keyboard::Keyboard::new(),
+        "Settings" => settings::Settings::new(),
+    }
+}
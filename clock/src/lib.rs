@@ -1,29 +1,40 @@
 #![no_std]
 
-use core::fmt::Debug;
-
 use chrono::Timelike;
+use embassy_time::{Duration, with_timeout};
 use embedded_graphics::{
-    draw_target::DrawTarget,
-    mono_font::{MonoTextStyle, ascii::FONT_10X20},
+    mono_font::{
+        MonoTextStyle,
+        ascii::{FONT_6X10, FONT_10X20},
+    },
     pixelcolor::BinaryColor,
     prelude::*,
     primitives::PrimitiveStyle,
     text::{Alignment, Text},
 };
-use shared::Application;
+use shared::{
+    Application, Board, Key, KeyEvent, Keypad, Peripherals, Rtc, SystemRequest, SystemResponse,
+    budget::Budget, settings::Settings, soft_keys::SoftKeys, widget,
+};
 
-pub struct Clock;
+pub struct Clock {
+    clock_24h: bool,
+    /// Showing the "Set time" dialog, which labels its own keys.
+    setting: bool,
+}
 
 impl Clock {
     pub fn new() -> Self {
-        Self
+        Self {
+            clock_24h: true,
+            setting: false,
+        }
     }
 }
 
 impl Default for Clock {
     fn default() -> Self {
-        Self
+        Self::new()
     }
 }
 
@@ -45,19 +56,31 @@ fn to_char(digit: u32) -> char {
 }
 
 impl Application for Clock {
-    async fn run<D: DrawTarget<Color = BinaryColor>>(
+    async fn on_start(&mut self, board: &mut impl Board) {
+        self.clock_24h = Settings::load(board.storage()).await.clock_24h;
+    }
+
+    async fn run(
         &mut self,
-        _vibration_motor: &mut impl shared::VibrationMotor,
-        _buzzer: &mut impl shared::Buzzer,
-        display: &mut D,
-        _keypad: &mut impl shared::Keypad,
-        rtc: &mut impl shared::Rtc,
-        _backlight: &mut impl shared::Backlight,
-        _system_response: Option<[u8; 64]>,
-    ) -> Option<shared::UsbTx>
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+        board: &mut impl Board,
+        system_response: Option<SystemResponse>,
+    ) -> Option<SystemRequest> {
+        if let Some(SystemResponse::Failed(e)) = system_response {
+            log::warn!("time not set: {:?}", e);
+            let message = heapless::String::try_from("Time not set").unwrap();
+            return Some(SystemRequest::Notify(message));
+        }
+
+        let timestamp = board.rtc().timestamp();
+        let Peripherals {
+            keypad, display, ..
+        } = board.peripherals();
+        if self.setting {
+            let timestamp = widget::date_time("Set time", timestamp, keypad, display).await;
+            self.setting = false;
+            return timestamp.map(SystemRequest::SetTime);
+        }
+
         let fill = PrimitiveStyle::with_fill(BinaryColor::On);
         display
             .bounding_box()
@@ -66,11 +89,19 @@ impl Application for Clock {
             .unwrap();
 
         let character_style = MonoTextStyle::new(&FONT_10X20, BinaryColor::Off);
-        let now = chrono::DateTime::<chrono::Utc>::from_timestamp(rtc.timestamp(), 0).unwrap();
+        // the app's RTC reads local time, so there is no zone left to apply
+        let now = chrono::DateTime::from_timestamp(timestamp, 0)
+            .unwrap()
+            .naive_utc();
         let mut text: heapless::String<8> = heapless::String::new();
 
-        text.push(to_char(now.hour() / 10)).unwrap();
-        text.push(to_char(now.hour() % 10)).unwrap();
+        let hour = if self.clock_24h {
+            now.hour()
+        } else {
+            now.hour12().1
+        };
+        text.push(to_char(hour / 10)).unwrap();
+        text.push(to_char(hour % 10)).unwrap();
         text.push(':').unwrap();
         text.push(to_char(now.minute() / 10)).unwrap();
         text.push(to_char(now.minute() % 10)).unwrap();
@@ -86,8 +117,45 @@ impl Application for Clock {
         )
         .draw(display)
         .unwrap();
-        embassy_time::Timer::after_millis(10).await;
+        if !self.clock_24h {
+            let meridiem = if now.hour12().0 { "PM" } else { "AM" };
+            Text::with_alignment(
+                meridiem,
+                display.bounding_box().center() + Point::new(0, 18),
+                MonoTextStyle::new(&FONT_6X10, BinaryColor::Off),
+                Alignment::Center,
+            )
+            .draw(display)
+            .unwrap();
+        }
+
+        // Select sets the time, in the next `run` so that the dialog gets
+        // the soft key row, otherwise redraw it every 10ms
+        if let Ok(KeyEvent::Down(Key::Select)) =
+            with_timeout(Duration::from_millis(10), keypad.event()).await
+        {
+            self.setting = true;
+        }
 
         None
     }
+
+    // draws the time itself, full screen
+    fn status_bar(&self) -> bool {
+        false
+    }
+
+    fn soft_keys(&self) -> SoftKeys<'static, ()> {
+        if self.setting {
+            SoftKeys::new()
+        } else {
+            SoftKeys::new().select("Set time", ())
+        }
+    }
+
+    // `run` may wait for the user to set the time, which would lose what they
+    // had entered if it were pre-empted
+    fn budget(&self) -> Budget {
+        Budget::INTERACTIVE
+    }
 }
@@ -1,15 +1,15 @@
 #![no_std]
 
-use core::fmt::Debug;
-
 use defmt::*;
 use embedded_graphics::{
-    draw_target::DrawTarget,
     pixelcolor::BinaryColor,
     prelude::*,
     primitives::{PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, StrokeAlignment, Triangle},
 };
-use shared::{Application, Key, KeyEvent};
+use shared::{
+    Application, Backlight, Board, Buzzer, Key, KeyEvent, Keypad, Peripherals, VibrationMotor,
+    budget::Budget, vibration,
+};
 
 pub struct HardwareTest(i32);
 
@@ -26,19 +26,20 @@ impl Default for HardwareTest {
 }
 
 impl Application for HardwareTest {
-    async fn run<D: DrawTarget<Color = BinaryColor>>(
+    async fn run(
         &mut self,
-        vibration_motor: &mut impl shared::VibrationMotor,
-        buzzer: &mut impl shared::Buzzer,
-        display: &mut D,
-        keypad: &mut impl shared::Keypad,
-        _rtc: &mut impl shared::Rtc,
-        backlight: &mut impl shared::Backlight,
-        _system_response: Option<[u8; 64]>,
-    ) -> Option<shared::UsbTx>
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+        board: &mut impl Board,
+        _system_response: Option<shared::SystemResponse>,
+    ) -> Option<shared::SystemRequest> {
+        let Peripherals {
+            vibration_motor,
+            buzzer,
+            display,
+            keypad,
+            backlight,
+            ..
+        } = board.peripherals();
+
         let thin_stroke = PrimitiveStyle::with_stroke(BinaryColor::Off, 1);
         let border_stroke = PrimitiveStyleBuilder::new()
             .stroke_color(BinaryColor::Off)
@@ -98,7 +99,7 @@ impl Application for HardwareTest {
                 println!("Three");
                 backlight.off();
             }
-            KeyEvent::Up(_) => {}
+            KeyEvent::Up(_) | KeyEvent::LongPress(_) | KeyEvent::Repeat(_) => {}
             KeyEvent::Down(Key::Select) => {
                 println!("Select");
             }
@@ -107,12 +108,17 @@ impl Application for HardwareTest {
             }
             KeyEvent::Down(Key::Asterisk) => {
                 println!("*");
+                backlight.set_brightness(30);
+                vibration_motor.set_intensity(30);
             }
             KeyEvent::Down(Key::Zero) => {
                 println!("0");
+                vibration::play(&vibration::NOTIFICATION, vibration_motor).await;
             }
             KeyEvent::Down(Key::Hash) => {
                 println!("#");
+                backlight.set_brightness(100);
+                vibration_motor.set_intensity(100);
             }
         }
 
@@ -132,4 +138,25 @@ impl Application for HardwareTest {
 
         None
     }
+
+    // `run` waits for a key
+    fn budget(&self) -> Budget {
+        Budget::INTERACTIVE
+    }
+
+    async fn on_suspend(&mut self, board: &mut impl Board) {
+        release(board);
+    }
+
+    async fn on_exit(&mut self, board: &mut impl Board) {
+        release(board);
+    }
+}
+
+// everything here is switched on by key presses, so switch it all off again
+// whenever the test is interrupted
+fn release(board: &mut impl Board) {
+    board.buzzer().mute();
+    board.vibration_motor().stop();
+    board.backlight().off();
 }
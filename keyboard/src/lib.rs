@@ -1,16 +1,13 @@
 #![no_std]
 
-use core::fmt::Debug;
-
 use embedded_graphics::{
-    draw_target::DrawTarget,
     // mono_font::{MonoTextStyle, ascii::FONT_10X20},
     pixelcolor::BinaryColor,
     prelude::*,
     primitives::PrimitiveStyle,
     // text::{Alignment, Text},
 };
-use shared::{Application, Key, KeyEvent};
+use shared::{Application, Board, Key, KeyEvent, Keypad, SystemRequest, UsbTx, budget::Budget};
 
 pub struct Keyboard;
 
@@ -27,19 +24,12 @@ impl Default for Keyboard {
 }
 
 impl Application for Keyboard {
-    async fn run<D: DrawTarget<Color = BinaryColor>>(
+    async fn run(
         &mut self,
-        _vibration_motor: &mut impl shared::VibrationMotor,
-        _buzzer: &mut impl shared::Buzzer,
-        display: &mut D,
-        keypad: &mut impl shared::Keypad,
-        _rtc: &mut impl shared::Rtc,
-        _backlight: &mut impl shared::Backlight,
-        _system_response: Option<[u8; 64]>,
-    ) -> Option<shared::UsbTx>
-    where
-        <D as DrawTarget>::Error: Debug,
-    {
+        board: &mut impl Board,
+        _system_response: Option<shared::SystemResponse>,
+    ) -> Option<SystemRequest> {
+        let display = board.display();
         let fill = PrimitiveStyle::with_fill(BinaryColor::Off);
         display
             .bounding_box()
@@ -59,24 +49,35 @@ impl Application for Keyboard {
         // .draw(display)
         // .unwrap();
 
-        match keypad.event().await {
-            KeyEvent::Down(Key::Down) => Some(shared::UsbTx::HidChar('d')),
-            KeyEvent::Down(Key::Up) => Some(shared::UsbTx::HidChar('u')),
-            KeyEvent::Down(Key::One) => Some(shared::UsbTx::HidChar('1')),
-            KeyEvent::Down(Key::Two) => Some(shared::UsbTx::HidChar('2')),
-            KeyEvent::Down(Key::Four) => Some(shared::UsbTx::HidChar('4')),
-            KeyEvent::Down(Key::Five) => Some(shared::UsbTx::HidChar('5')),
-            KeyEvent::Down(Key::Six) => Some(shared::UsbTx::HidChar('6')),
-            KeyEvent::Down(Key::Eight) => Some(shared::UsbTx::HidChar('8')),
-            KeyEvent::Down(Key::Seven) => Some(shared::UsbTx::HidChar('7')),
-            KeyEvent::Down(Key::Nine) => Some(shared::UsbTx::HidChar('9')),
-            KeyEvent::Down(Key::Three) => Some(shared::UsbTx::HidChar('3')),
-            KeyEvent::Down(Key::Select) => Some(shared::UsbTx::HidChar('s')),
-            KeyEvent::Down(Key::Cancel) => Some(shared::UsbTx::HidChar('c')),
-            KeyEvent::Down(Key::Asterisk) => Some(shared::UsbTx::HidChar('*')),
-            KeyEvent::Down(Key::Zero) => Some(shared::UsbTx::HidChar('0')),
-            KeyEvent::Down(Key::Hash) => Some(shared::UsbTx::HidChar('#')),
-            KeyEvent::Up(_) => None,
-        }
+        // held keys repeat, as on a real keyboard
+        let key = match board.keypad().event().await {
+            KeyEvent::Down(key) | KeyEvent::Repeat(key) => key,
+            KeyEvent::Up(_) | KeyEvent::LongPress(_) => return None,
+        };
+        let c = match key {
+            Key::Down => 'd',
+            Key::Up => 'u',
+            Key::One => '1',
+            Key::Two => '2',
+            Key::Four => '4',
+            Key::Five => '5',
+            Key::Six => '6',
+            Key::Eight => '8',
+            Key::Seven => '7',
+            Key::Nine => '9',
+            Key::Three => '3',
+            Key::Select => 's',
+            Key::Cancel => 'c',
+            Key::Asterisk => '*',
+            Key::Zero => '0',
+            Key::Hash => '#',
+        };
+
+        Some(SystemRequest::Usb(UsbTx::HidChar(c)))
+    }
+
+    // `run` waits for a key
+    fn budget(&self) -> Budget {
+        Budget::INTERACTIVE
     }
 }
@@ -0,0 +1,152 @@
+//! Multi-tap entry one press at a time, for callers that wait for keys and
+//! time taps out themselves.
+
+/// A key that types when tapped.
+pub trait Letters: Copy + PartialEq {
+    /// What repeated taps cycle through, in order.  Empty for keys that do
+    /// not type anything.
+    fn letters(self) -> &'static [u8];
+}
+
+/// Text typed so far, and the letter still being cycled through: repeated
+/// taps on a key cycle through its letters until a different key is pressed
+/// or the letter is decided.
+pub struct Input<K, const N: usize> {
+    text: heapless::String<N>,
+    tentative: Option<(K, usize)>,
+}
+
+impl<K: Letters, const N: usize> Default for Input<K, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Letters, const N: usize> Input<K, N> {
+    pub const fn new() -> Self {
+        Self {
+            text: heapless::String::new(),
+            tentative: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The letter still being cycled through, if any.
+    pub fn tentative(&self) -> Option<char> {
+        let (key, index) = self.tentative?;
+        key.letters().get(index).copied().map(char::from)
+    }
+
+    /// Returns `false` if `key` does not type anything.
+    pub fn press(&mut self, key: K) -> bool {
+        let count = key.letters().len();
+        if count == 0 {
+            return false;
+        }
+
+        self.tentative = match self.tentative {
+            Some((last, index)) if last == key => {
+                let next = index.wrapping_add(1);
+                Some((key, if next < count { next } else { 0 }))
+            }
+            Some(_) | None => {
+                self.decide();
+                Some((key, 0))
+            }
+        };
+        true
+    }
+
+    /// Appends the tentative letter to the text.  Letters past the capacity
+    /// are dropped.
+    pub fn decide(&mut self) {
+        if let Some(c) = self.tentative() {
+            let _ = self.text.push(c);
+        }
+        self.tentative = None;
+    }
+
+    /// Removes the tentative letter, or the last decided one.  Returns
+    /// `false` if there was nothing to remove.
+    pub fn backspace(&mut self) -> bool {
+        self.tentative.take().is_some() || self.text.pop().is_some()
+    }
+
+    /// Turns the tentative letter into the last one on `key`, if `key` is
+    /// the one being cycled through, and decides it.
+    pub fn hold(&mut self, key: K) {
+        if let Some((last, _)) = self.tentative
+            && last == key
+            && let Some(index) = key.letters().len().checked_sub(1)
+        {
+            self.tentative = Some((key, index));
+            self.decide();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.tentative = None;
+    }
+
+    pub fn into_text(mut self) -> heapless::String<N> {
+        self.decide();
+        self.text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Key {
+        Two,
+        Four,
+        Seven,
+        Hash,
+    }
+
+    impl Letters for Key {
+        fn letters(self) -> &'static [u8] {
+            match self {
+                Key::Two => b"ABC2",
+                Key::Four => b"GHI4",
+                Key::Seven => b"PQRS7",
+                Key::Hash => b"",
+            }
+        }
+    }
+
+    #[test]
+    fn test_input() {
+        let mut input = Input::<Key, 8>::new();
+
+        input.press(Key::Four);
+        input.press(Key::Four);
+        assert_eq!(input.tentative(), Some('H'));
+        input.press(Key::Four);
+        input.press(Key::Two);
+        assert_eq!(input.text(), "I");
+        input.press(Key::Two);
+        input.press(Key::Two);
+        input.press(Key::Two);
+        input.press(Key::Two);
+        assert_eq!(input.tentative(), Some('A'));
+        assert!(!input.press(Key::Hash));
+
+        assert!(input.backspace());
+        assert!(input.backspace());
+        assert!(!input.backspace());
+
+        input.press(Key::Seven);
+        input.hold(Key::Seven);
+        assert_eq!(input.text(), "7");
+        assert_eq!(input.tentative(), None);
+        input.clear();
+        assert_eq!(input.text(), "");
+    }
+}
@@ -1,8 +1,7 @@
 use core::{ascii::Char, future::Future};
 
-use defmt::Format;
-
-#[derive(Debug, PartialEq, Format, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Event<B> {
     Down(B),
     Up(B),
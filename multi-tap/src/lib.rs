@@ -2,18 +2,19 @@
 #![feature(ascii_char)]
 #![feature(ascii_char_variants)]
 #![feature(trivial_bounds)]
-#![feature(let_chains)]
 
 pub mod char;
+pub mod input;
 pub mod keypad;
 
 use core::{ascii::Char, future::Future};
 
-use defmt::Format;
 use futures::{future, future::Either, pin_mut};
+pub use input::{Input, Letters};
 pub use keypad::*;
 
-#[derive(Debug, PartialEq, Format, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Event {
     Tentative(Char),
     Decided(Char),
@@ -48,76 +49,66 @@ where
     {
         // if something has just been decided
         // still emit the next tentative
-        if let Some(pending) = self.pending {
-            self.pending = None;
+        if let Some(pending) = self.pending.take() {
             self.last_emitted = Some(pending);
             return pending;
         }
 
-        let event_future = self.keypad.event();
-        pin_mut!(event_future);
         pin_mut!(timeout_future);
-
-        if self.last_press.is_some() {
-            match future::select(timeout_future, event_future).await {
-                Either::Left((..)) => {
-                    if let Some(Event::Tentative(e)) = self.last_emitted {
-                        self.last_emitted = None;
-                        self.last_press = None;
-
-                        return Event::Decided(e);
-                    }
-                }
-                Either::Right((keypad::Event::Down(e), _)) => {
-                    if let Some(p) = &self.last_press
-                        && *p != e
-                    {
-                        if let Some(Event::Tentative(f)) = self.last_emitted {
-                            self.last_emitted = Some(Event::Tentative(e.clone().into()));
-                            self.last_press = Some(e.clone());
-                            // TODO: panic if there is already a pending event
-                            self.pending = Some(Event::Tentative(e.clone().into()));
-
-                            return Event::Decided(f);
+        let mut timed_out = false;
+        loop {
+            let event = {
+                let event_future = self.keypad.event();
+                pin_mut!(event_future);
+
+                if self.last_press.is_some() && !timed_out {
+                    match future::select(timeout_future.as_mut(), event_future).await {
+                        Either::Left(((), _)) => {
+                            timed_out = true;
+                            self.last_press = None;
+                            if let Some(Event::Tentative(e)) = self.last_emitted.take() {
+                                return Event::Decided(e);
+                            }
+                            continue;
                         }
-                    } else {
-                        self.last_press = Some(e.clone());
-                        self.last_emitted = match self.last_emitted {
-                            Some(Event::Tentative(c)) => Some(Event::Tentative(char::next_char(c))),
-                            Some(Event::Decided(_)) => None,
-                            None => Some(Event::Tentative(e.clone().into())),
-                        };
-
-                        return self.last_emitted.unwrap();
+                        Either::Right((event, _)) => event,
                     }
+                } else {
+                    event_future.await
                 }
-                Either::Right((keypad::Event::Up(_), _)) => {}
-            }
-        } else if let keypad::Event::Down(e) = event_future.await {
-            if let Some(p) = &self.last_press
-                && *p != e
+            };
+
+            if let keypad::Event::Down(button) = event
+                && let Some(emitted) = self.press(button)
             {
-                if let Some(Event::Tentative(f)) = self.last_emitted {
-                    self.last_emitted = Some(Event::Tentative(e.clone().into()));
-                    self.last_press = Some(e.clone());
-                    // TODO: panic if there is already a pending event
-                    self.pending = Some(Event::Tentative(e.clone().into()));
+                return emitted;
+            }
+        }
+    }
 
-                    return Event::Decided(f);
+    /// What a press of `button` emits, if anything.  A press of a different
+    /// button decides the tentative character, and its own follows as
+    /// pending.
+    fn press(&mut self, button: KEYPAD::Button) -> Option<Event> {
+        if self.last_press.as_ref().is_some_and(|last| *last != button) {
+            let tentative = Event::Tentative(button.clone().into());
+            self.last_press = Some(button);
+            return match self.last_emitted.replace(tentative) {
+                Some(Event::Tentative(decided)) => {
+                    self.pending = Some(tentative);
+                    Some(Event::Decided(decided))
                 }
-            } else {
-                self.last_press = Some(e.clone());
-                self.last_emitted = match self.last_emitted {
-                    Some(Event::Tentative(c)) => Some(Event::Tentative(char::next_char(c))),
-                    Some(Event::Decided(_)) => None,
-                    None => Some(Event::Tentative(e.clone().into())),
-                };
-
-                return self.last_emitted.unwrap();
-            }
+                Some(Event::Decided(_)) | None => Some(tentative),
+            };
         }
 
-        panic!()
+        self.last_press = Some(button.clone());
+        self.last_emitted = match self.last_emitted {
+            Some(Event::Tentative(c)) => Some(Event::Tentative(char::next_char(c))),
+            Some(Event::Decided(_)) => None,
+            None => Some(Event::Tentative(button.into())),
+        };
+        self.last_emitted
     }
 }
 
@@ -129,7 +120,7 @@ mod test {
 
     use super::*;
 
-    #[derive(Debug, PartialEq, Format, Copy, Clone)]
+    #[derive(Debug, PartialEq, Copy, Clone)]
     pub enum Key {
         One,
         Two,
@@ -155,6 +146,11 @@ mod test {
     impl Keypad for TwoKeys<'_> {
         type Button = Key;
 
+        #[expect(
+            clippy::indexing_slicing,
+            clippy::arithmetic_side_effects,
+            reason = "running out of presses panics"
+        )]
         async fn event(&mut self) -> crate::keypad::Event<Self::Button> {
             let result = self.0[self.1];
             self.1 += 1;
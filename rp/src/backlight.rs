@@ -1,23 +1,46 @@
 use embassy_rp::{
-    gpio::{Level, Output},
-    peripherals::PIN_15,
+    peripherals::{PIN_15, PWM_SLICE7},
+    pwm::{Config, Pwm, SetDutyCycle},
 };
 use shared::Backlight;
 
-pub struct Light<'a>(Output<'a>);
+pub struct Light<'a> {
+    pwm: Pwm<'a>,
+    brightness: u8,
+    on: bool,
+}
 
 impl Light<'_> {
-    pub fn new(pin: PIN_15) -> Self {
-        Self(Output::new(pin, Level::Low))
+    pub fn new(slice: PWM_SLICE7, pin: PIN_15) -> Self {
+        // the default config runs at a couple of kHz, well past any flicker
+        let mut pwm = Pwm::new_output_b(slice, pin, Config::default());
+        pwm.set_duty_cycle_fully_off().unwrap();
+        Self {
+            pwm,
+            brightness: 100,
+            on: false,
+        }
+    }
+
+    fn update(&mut self) {
+        let duty = if self.on { self.brightness.min(100) } else { 0 };
+        self.pwm.set_duty_cycle_percent(duty).unwrap();
     }
 }
 
 impl Backlight for Light<'_> {
     fn on(&mut self) {
-        self.0.set_high();
+        self.on = true;
+        self.update();
     }
 
     fn off(&mut self) {
-        self.0.set_low();
+        self.on = false;
+        self.update();
+    }
+
+    fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+        self.update();
     }
 }
@@ -0,0 +1,38 @@
+use shared::{Peripherals, keypad::Gestures};
+
+use crate::{backlight, button, buzzer, display, keypad, rtc, storage, vibration_motor};
+
+pub struct Board<'a> {
+    pub vibration_motor: vibration_motor::Motor<'a>,
+    pub buzzer: buzzer::Beeper,
+    pub display: display::Display<'a>,
+    pub keypad: Gestures<keypad::ContactKeypad<'a>>,
+    pub rtc: rtc::Shared,
+    pub backlight: backlight::Light<'a>,
+    pub storage: storage::Store<'a>,
+    pub power: button::Button<'a>,
+}
+
+impl<'a> shared::Board for Board<'a> {
+    type VibrationMotor = vibration_motor::Motor<'a>;
+    type Buzzer = buzzer::Beeper;
+    type Display = display::Display<'a>;
+    type Keypad = Gestures<keypad::ContactKeypad<'a>>;
+    type Rtc = rtc::Shared;
+    type Backlight = backlight::Light<'a>;
+    type Storage = storage::Store<'a>;
+    type PowerButton = button::Button<'a>;
+
+    fn peripherals(&mut self) -> Peripherals<'_, Self> {
+        Peripherals {
+            vibration_motor: &mut self.vibration_motor,
+            buzzer: &mut self.buzzer,
+            display: &mut self.display,
+            keypad: &mut self.keypad,
+            rtc: &mut self.rtc,
+            backlight: &mut self.backlight,
+            storage: &mut self.storage,
+            power_button: &mut self.power,
+        }
+    }
+}
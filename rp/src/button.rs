@@ -16,4 +16,8 @@ impl PowerButton for Button<'_> {
     async fn was_pressed(&mut self) -> bool {
         self.0.is_low()
     }
+
+    async fn wait_for_press(&mut self) {
+        self.0.wait_for_low().await;
+    }
 }
@@ -1,49 +1,124 @@
+use embassy_futures::select::{Either, select};
 use embassy_rp::{
     peripherals::{PIN_21, PWM_SLICE2},
-    pwm::{Config, Pwm, SetDutyCycle},
+    pwm::{Config, Pwm},
 };
-use shared::Buzzer;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, signal::Signal};
+use embassy_time::{Duration, Timer};
+use shared::{Buzzer, audio::amplitude};
 
-pub struct Beeper<'a>(Pwm<'a>, u16);
+/// How long notes take to fade in and out, so that they start and stop
+/// without a click.  Well inside the gap [`shared::audio`] leaves between
+/// notes.
+const RAMP: Duration = Duration::from_millis(5);
+const STEPS: u16 = 5;
 
-impl Beeper<'_> {
-    pub fn new(slice: PWM_SLICE2, pin: PIN_21) -> Self {
-        Self(Pwm::new_output_b(slice, pin, Config::default()), 0)
-    }
+#[derive(Clone, Copy)]
+struct Tone {
+    frequency: u16,
+    volume: u8,
+    muted: bool,
+}
 
-    fn update(&mut self) {
-        let mut c: embassy_rp::pwm::Config = Default::default();
-        if self.1 == 0 {
-            self.0.set_duty_cycle_percent(0).unwrap();
+impl Tone {
+    const SILENT: Self = Self {
+        frequency: 0,
+        volume: 100,
+        muted: true,
+    };
+
+    /// Thousandths of full scale.
+    fn level(&self) -> u16 {
+        if self.muted || self.frequency == 0 {
+            0
         } else {
-            let divider = 16u8;
-            let period =
-                (embassy_rp::clocks::clk_sys_freq() / (self.1 as u32 * divider as u32)) as u16 - 1;
+            amplitude(self.volume)
+        }
+    }
+}
 
-            c.top = period;
-            c.divider = divider.into();
+static TONE: Signal<CriticalSectionRawMutex, Tone> = Signal::new();
 
-            self.0.set_config(&c);
-            self.0.set_duty_cycle_percent(90).unwrap();
-        }
+/// Hands every change to [`envelope`], which owns the PWM.
+pub struct Beeper(Tone);
+
+impl Beeper {
+    pub fn new() -> Self {
+        Self(Tone::SILENT)
+    }
+
+    fn update(&mut self) {
+        TONE.signal(self.0);
+    }
+}
+
+impl Default for Beeper {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl Buzzer for Beeper<'_> {
+impl Buzzer for Beeper {
     fn mute(&mut self) {
-        self.0.set_duty_cycle_percent(0).unwrap();
+        self.0.muted = true;
+        self.update();
     }
 
     fn unmute(&mut self) {
-        self.0.set_duty_cycle_percent(90).unwrap();
+        self.0.muted = false;
+        self.update();
     }
 
-    fn set_volume(&mut self, _volume: u8) {
-        // self.0.set_duty_cycle_percent(volume).unwrap();
+    fn set_volume(&mut self, volume: u8) {
+        self.0.volume = volume;
+        self.update();
     }
 
     fn set_frequency(&mut self, frequency: u16) {
-        self.1 = frequency;
+        self.0.frequency = frequency;
         self.update();
     }
 }
+
+fn configure(config: &mut Config, frequency: u16, level: u16) {
+    if frequency != 0 {
+        let divider = 16u8;
+        config.divider = divider.into();
+        config.top =
+            (embassy_rp::clocks::clk_sys_freq() / (frequency as u32 * divider as u32)) as u16 - 1;
+    }
+    // a square wave is loudest when on for half of each period, and the
+    // duty cycle below that follows loudness closely enough
+    config.compare_b = (u32::from(config.top) * u32::from(level) / 2000) as u16;
+}
+
+/// Drives the buzzer pin, ramping towards each level [`Beeper`] asks for
+/// over [`RAMP`] rather than jumping to it.
+#[embassy_executor::task]
+pub async fn envelope(slice: PWM_SLICE2, pin: PIN_21) {
+    let mut config = Config::default();
+    configure(&mut config, 0, 0);
+    let mut pwm = Pwm::new_output_b(slice, pin, config.clone());
+    let mut tone = Tone::SILENT;
+    let mut level = 0;
+
+    loop {
+        let target = tone.level();
+        let step = (target.max(level) / STEPS).max(1);
+        level = if target > level {
+            (level + step).min(target)
+        } else {
+            level.saturating_sub(step).max(target)
+        };
+        configure(&mut config, tone.frequency, level);
+        pwm.set_config(&config);
+
+        if level == target {
+            tone = TONE.wait().await;
+        } else if let Either::Second(next) =
+            select(Timer::after(RAMP / u32::from(STEPS)), TONE.wait()).await
+        {
+            tone = next;
+        }
+    }
+}
@@ -86,6 +86,16 @@ impl<'a> DrawTarget for Display<'a> {
     }
 }
 
+impl shared::Display for Display<'_> {
+    fn set_contrast(&mut self, contrast: u8) {
+        // the operating voltage is 7 bits, but the panel is blank below
+        // about 40 and solid black above about 90
+        const VOP: core::ops::RangeInclusive<u16> = 40..=90;
+        let vop = VOP.start() + u16::from(contrast.min(100)) * (VOP.end() - VOP.start()) / 100;
+        self.0.set_contrast(vop as u8).unwrap();
+    }
+}
+
 impl Dimensions for Display<'_> {
     fn bounding_box(&self) -> Rectangle {
         self.0.bounding_box()
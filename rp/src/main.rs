@@ -25,6 +25,7 @@ pub static PICOTOOL_ENTRIES: [embassy_rp::binary_info::EntryAddr; 6] = [
 
 use core::cell::RefCell;
 
+use applications::Applications;
 use assign_resources::assign_resources;
 use defmt::unwrap;
 use defmt_rtt as _;
@@ -41,14 +42,18 @@ use embassy_rp::{
 };
 use embassy_sync::blocking_mutex::{Mutex, raw::NoopRawMutex};
 use panic_probe as _;
+use shared::{keypad::Gestures, registry::Launcher};
 use static_cell::StaticCell;
 
 mod backlight;
+mod board;
 mod button;
 mod buzzer;
 mod display;
 mod keypad;
 mod rtc;
+mod storage;
+mod system;
 mod usb;
 mod vibration_motor;
 
@@ -66,7 +71,7 @@ static mut CORE1_STACK: Stack<4096> = Stack::new();
 static EXECUTOR1: StaticCell<Executor> = StaticCell::new();
 
 #[embassy_executor::main]
-async fn main(_spawner: Spawner) {
+async fn main(spawner: Spawner) {
     let p = embassy_rp::init(Default::default());
     let r = split_resources!(p);
     spawn_core1(
@@ -74,15 +79,19 @@ async fn main(_spawner: Spawner) {
         unsafe { &mut *core::ptr::addr_of_mut!(CORE1_STACK) },
         move || {
             let executor1 = EXECUTOR1.init(Executor::new());
-            executor1.run(|spawner| unwrap!(spawner.spawn(usb::big_usb_task(spawner, r.usbs))));
+            executor1.run(|spawner| {
+                unwrap!(spawner.spawn(usb::big_usb_task(spawner, r.usbs)));
+                unwrap!(spawner.spawn(usb::answer_host()));
+            });
         },
     );
 
-    let mut power = button::Button::new(p.PIN_28);
+    let power = button::Button::new(p.PIN_28);
 
-    let mut vibration_motor = vibration_motor::Motor::new(p.PIN_2);
-    let mut buzzer = buzzer::Beeper::new(p.PWM_SLICE2, p.PIN_21);
-    let mut clock = rtc::Clock::new(p.I2C1, p.PIN_46, p.PIN_47);
+    let vibration_motor = vibration_motor::Motor::new(p.PWM_SLICE1, p.PIN_2);
+    let buzzer = buzzer::Beeper::new();
+    unwrap!(spawner.spawn(buzzer::envelope(p.PWM_SLICE2, p.PIN_21)));
+    let rtc = rtc::Shared::new(rtc::Clock::new(p.I2C1, p.PIN_46, p.PIN_47));
 
     let mut display_config = spi::Config::default();
     display_config.frequency = 4_000_000;
@@ -93,81 +102,28 @@ async fn main(_spawner: Spawner) {
         p.PIN_32,
         display_config,
     )));
-    let mut display = display::Display::new(&spi_bus, p.PIN_37, p.PIN_36, p.PIN_33);
+    let display = display::Display::new(&spi_bus, p.PIN_37, p.PIN_36, p.PIN_33);
 
-    let mut keypad = keypad::ContactKeypad::new(
+    let keypad = Gestures::new(keypad::ContactKeypad::new(
         p.PIN_16, p.PIN_12, p.PIN_9, p.PIN_8, p.PIN_17, p.PIN_13, p.PIN_7, p.PIN_18, p.PIN_14,
         p.PIN_6, p.PIN_19, p.PIN_11, p.PIN_5, p.PIN_20, p.PIN_10, p.PIN_4,
-    );
-
-    let mut backlight = backlight::Light::new(p.PIN_15);
-
-    let items = ["Clock", "Hardware Test", "Keyboard"];
-    let mut menu = shared::menu::Menu::new(&items);
-
-    loop {
-        let i = loop {
-            if let Some(index) = menu.process(&mut keypad, &mut display).await {
-                break index;
-            }
-        };
-        if i == 0 {
-            let clock_app = clock::Clock;
-            match shared::run_app(
-                clock_app,
-                &mut vibration_motor,
-                &mut buzzer,
-                &mut display,
-                &mut keypad,
-                &mut clock,
-                &mut backlight,
-                &mut power,
-                usb::RX_CHANNEL.try_receive().ok(),
-            )
-            .await
-            {
-                Some(shared::UsbTx::HidChar(c)) => usb::HID_TX_CHANNEL.send(c).await,
-                Some(shared::UsbTx::CdcBuffer(b)) => usb::CDC_TX_CHANNEL.send(b).await,
-                None => {}
-            }
-        } else if i == 2 {
-            let keyboard = keyboard::Keyboard;
-            match shared::run_app(
-                keyboard,
-                &mut vibration_motor,
-                &mut buzzer,
-                &mut display,
-                &mut keypad,
-                &mut clock,
-                &mut backlight,
-                &mut power,
-                usb::RX_CHANNEL.try_receive().ok(),
-            )
-            .await
-            {
-                Some(shared::UsbTx::HidChar(c)) => usb::HID_TX_CHANNEL.send(c).await,
-                Some(shared::UsbTx::CdcBuffer(b)) => usb::CDC_TX_CHANNEL.send(b).await,
-                None => {}
-            }
-        } else {
-            let hardware_test = hardware_test::HardwareTest::default();
-            match shared::run_app(
-                hardware_test,
-                &mut vibration_motor,
-                &mut buzzer,
-                &mut display,
-                &mut keypad,
-                &mut clock,
-                &mut backlight,
-                &mut power,
-                usb::RX_CHANNEL.try_receive().ok(),
-            )
-            .await
-            {
-                Some(shared::UsbTx::HidChar(c)) => usb::HID_TX_CHANNEL.send(c).await,
-                Some(shared::UsbTx::CdcBuffer(b)) => usb::CDC_TX_CHANNEL.send(b).await,
-                None => {}
-            }
-        }
-    }
+    ));
+
+    let backlight = backlight::Light::new(p.PWM_SLICE7, p.PIN_15);
+    let storage = storage::Store::new(p.FLASH, p.DMA_CH0);
+
+    let mut board = board::Board {
+        vibration_motor,
+        buzzer,
+        display,
+        keypad,
+        rtc,
+        backlight,
+        storage,
+        power,
+    };
+
+    Launcher::<Applications>::new()
+        .run(&mut board, &mut system::System::default())
+        .await;
 }
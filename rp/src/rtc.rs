@@ -1,5 +1,8 @@
+use core::cell::RefCell;
+
 use embassy_rp::peripherals::{I2C1, PIN_46, PIN_47};
-use shared::Rtc;
+use embassy_sync::blocking_mutex::{Mutex, raw::CriticalSectionRawMutex};
+use shared::{Rtc, RtcError};
 use unofficial_piicodev::Driver;
 
 pub struct Clock<'a>(
@@ -21,14 +24,47 @@ impl Clock<'_> {
             .unwrap(),
         )
     }
+}
+
+/// The one clock, reached through [`Shared`] by both the board and the task
+/// answering the host on the other core.
+static CLOCK: Mutex<CriticalSectionRawMutex, RefCell<Option<Clock<'static>>>> =
+    Mutex::new(RefCell::new(None));
+
+pub struct Shared;
 
-    // fn set_timestamp(&mut self, timestamp: i64) {
-    //     self.0.set_unix_time(timestamp.try_into().unwrap()).unwrap()
-    // }
+impl Shared {
+    pub fn new(clock: Clock<'static>) -> Self {
+        CLOCK.lock(|shared| *shared.borrow_mut() = Some(clock));
+        Self
+    }
+}
+
+impl Rtc for Shared {
+    fn timestamp(&mut self) -> i64 {
+        CLOCK.lock(|clock| {
+            clock
+                .borrow_mut()
+                .as_mut()
+                .map_or(0, |clock| clock.timestamp())
+        })
+    }
+
+    fn set_timestamp(&mut self, timestamp: i64) -> Result<(), RtcError> {
+        CLOCK.lock(|clock| match clock.borrow_mut().as_mut() {
+            Some(clock) => clock.set_timestamp(timestamp),
+            None => Err(RtcError::Bus),
+        })
+    }
 }
 
 impl Rtc for Clock<'_> {
     fn timestamp(&mut self) -> i64 {
         self.0.get_unix_time().unwrap().into()
     }
+
+    fn set_timestamp(&mut self, timestamp: i64) -> Result<(), RtcError> {
+        let timestamp = timestamp.try_into().map_err(|_| RtcError::OutOfRange)?;
+        self.0.set_unix_time(timestamp).map_err(|_| RtcError::Bus)
+    }
 }
@@ -0,0 +1,106 @@
+use core::ops::Range;
+
+use embassy_rp::{
+    flash::{Async, Flash},
+    peripherals::{DMA_CH0, FLASH},
+};
+use sequential_storage::{cache::NoCache, map};
+use shared::storage::{Error, MAX_KEY, MAX_VALUE, check_key};
+
+const FLASH_SIZE: usize = 2 * 1024 * 1024;
+/// The last 32K of flash, left out of `FLASH` in memory.x.  Records are
+/// appended across its sectors in turn, so no one sector wears out first,
+/// and each is only taken as written once its checksum is, so losing power
+/// part way through leaves the previous value in place.
+const RANGE: Range<u32> = (FLASH_SIZE - 32 * 1024) as u32..FLASH_SIZE as u32;
+
+type Key = [u8; MAX_KEY];
+
+fn key(name: &str) -> Result<Key, Error> {
+    check_key(name)?;
+    let mut key = [0; MAX_KEY];
+    key[..name.len()].copy_from_slice(name.as_bytes());
+    Ok(key)
+}
+
+fn error<E>(error: sequential_storage::Error<E>) -> Error {
+    match error {
+        sequential_storage::Error::FullStorage => Error::Full,
+        sequential_storage::Error::Corrupted { .. } => Error::Corrupt,
+        sequential_storage::Error::BufferTooSmall(_) | sequential_storage::Error::ItemTooBig => {
+            Error::TooLarge
+        }
+        _ => Error::Backend,
+    }
+}
+
+pub struct Store<'a> {
+    flash: Flash<'a, FLASH, Async, FLASH_SIZE>,
+    cache: NoCache,
+    /// Room for a key, a value and the header around them.
+    buffer: [u8; MAX_KEY + MAX_VALUE + 32],
+}
+
+impl Store<'_> {
+    pub fn new(flash: FLASH, dma: DMA_CH0) -> Self {
+        Self {
+            flash: Flash::new(flash, dma),
+            cache: NoCache::new(),
+            buffer: [0; MAX_KEY + MAX_VALUE + 32],
+        }
+    }
+}
+
+impl shared::Storage for Store<'_> {
+    async fn read(&mut self, name: &str, buffer: &mut [u8]) -> Result<Option<usize>, Error> {
+        let key = key(name)?;
+        let value = map::fetch_item::<Key, &[u8], _>(
+            &mut self.flash,
+            RANGE,
+            &mut self.cache,
+            &mut self.buffer,
+            &key,
+        )
+        .await
+        .map_err(error)?;
+
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        buffer
+            .get_mut(..value.len())
+            .ok_or(Error::TooLarge)?
+            .copy_from_slice(value);
+        Ok(Some(value.len()))
+    }
+
+    async fn write(&mut self, name: &str, value: &[u8]) -> Result<(), Error> {
+        let key = key(name)?;
+        if value.len() > MAX_VALUE {
+            return Err(Error::TooLarge);
+        }
+        map::store_item(
+            &mut self.flash,
+            RANGE,
+            &mut self.cache,
+            &mut self.buffer,
+            &key,
+            &value,
+        )
+        .await
+        .map_err(error)
+    }
+
+    async fn delete(&mut self, name: &str) -> Result<(), Error> {
+        let key = key(name)?;
+        map::remove_item(
+            &mut self.flash,
+            RANGE,
+            &mut self.cache,
+            &mut self.buffer,
+            &key,
+        )
+        .await
+        .map_err(error)
+    }
+}
@@ -0,0 +1,54 @@
+use core::sync::atomic::Ordering;
+
+use embassy_time::{Duration, with_timeout};
+use shared::{
+    RequestError, UsbRx, UsbTx, backlight::Timeout, keyguard::Keyguard, settings::Settings,
+};
+
+use crate::usb;
+
+/// How long to wait for room to queue a message for the host.
+const TX_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct System {
+    settings: Settings,
+}
+
+static KEYGUARD: Keyguard = Keyguard::new();
+static BACKLIGHT: Timeout = Timeout::new();
+
+impl shared::system::System for System {
+    fn keyguard(&self) -> &Keyguard {
+        &KEYGUARD
+    }
+
+    fn backlight(&self) -> &Timeout {
+        &BACKLIGHT
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    fn usb_connected(&self) -> bool {
+        usb::CONNECTED.load(Ordering::Relaxed)
+    }
+
+    async fn usb_tx(&mut self, tx: UsbTx) -> Result<(), RequestError> {
+        match tx {
+            UsbTx::HidChar(c) => usb::HID_TX_CHANNEL.send(c).await,
+            UsbTx::Cdc(message) => {
+                // the queue only stays full while the host is not reading
+                with_timeout(TX_TIMEOUT, usb::CDC_TX_CHANNEL.send(message))
+                    .await
+                    .map_err(|_| RequestError::Unavailable)?
+            }
+        }
+        Ok(())
+    }
+
+    fn usb_rx(&mut self) -> Option<UsbRx> {
+        usb::RX_CHANNEL.try_receive().ok()
+    }
+}
@@ -1,8 +1,11 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
 use defmt::info;
 use embassy_executor::Spawner;
 use embassy_futures::join::{join, join4};
 use embassy_rp::usb::Driver;
 use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel};
+use embassy_time::{Duration, with_timeout};
 use embassy_usb::{
     class::{
         cdc_acm, hid,
@@ -11,14 +14,20 @@ use embassy_usb::{
     control::OutResponse,
     driver::EndpointError,
 };
+use shared::protocol::{Decoder, MAX_FRAME, Message};
 use static_cell::StaticCell;
 use usbd_hid::descriptor::{KeyboardReport, SerializedDescriptor};
 
-use crate::{Irqs, Usbs};
+use crate::{Irqs, Usbs, rtc};
 
+/// Everything received from the host, for [`answer_host`] to sort.
+static FROM_HOST: Channel<CriticalSectionRawMutex, Message, 10> = Channel::new();
+/// Messages from the host for the running app.
 pub static RX_CHANNEL: Channel<CriticalSectionRawMutex, shared::UsbRx, 10> = Channel::new();
 pub static HID_TX_CHANNEL: Channel<CriticalSectionRawMutex, char, 10> = Channel::new();
-pub static CDC_TX_CHANNEL: Channel<CriticalSectionRawMutex, [u8; 64], 10> = Channel::new();
+pub static CDC_TX_CHANNEL: Channel<CriticalSectionRawMutex, Message, 10> = Channel::new();
+/// Whether a host has the CDC port open.
+pub static CONNECTED: AtomicBool = AtomicBool::new(false);
 
 #[embassy_executor::task]
 pub async fn big_usb_task(_spawner: Spawner, usbs: Usbs) {
@@ -77,16 +86,24 @@ pub async fn big_usb_task(_spawner: Spawner, usbs: Usbs) {
     let mut usb = builder.build();
     let usb_fut = usb.run();
 
-    let echo_fut = async {
-        class.wait_connection().await;
-        let mut buf = [0; 64];
+    let (mut sender, mut receiver) = class.split();
+    let rx_fut = async {
+        let mut decoder = Decoder::new();
+        loop {
+            receiver.wait_connection().await;
+            info!("CDC connected");
+            CONNECTED.store(true, Ordering::Relaxed);
+            let _ = receive(&mut receiver, &mut decoder).await;
+            CONNECTED.store(false, Ordering::Relaxed);
+            info!("CDC disconnected");
+            decoder = Decoder::new();
+        }
+    };
+    let tx_fut = async {
+        let mut pending = None;
         loop {
-            let n = class.read_packet(&mut buf).await.unwrap();
-            RX_CHANNEL.send(buf).await;
-            class
-                .write_packet(&CDC_TX_CHANNEL.receive().await[..n])
-                .await
-                .unwrap();
+            sender.wait_connection().await;
+            let _ = transmit(&mut sender, &mut pending).await;
         }
     };
 
@@ -119,7 +136,88 @@ pub async fn big_usb_task(_spawner: Spawner, usbs: Usbs) {
     let out_fut = async {
         reader.run(false, &mut request_handler).await;
     };
-    join4(usb_fut, echo_fut, log_fut, join(in_fut, out_fut)).await;
+    join4(
+        usb_fut,
+        join(rx_fut, tx_fut),
+        log_fut,
+        join(in_fut, out_fut),
+    )
+    .await;
+}
+
+/// How long a received message waits for room in [`RX_CHANNEL`] before it is
+/// dropped, so that nobody reading it cannot stall the host.
+const RX_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Answers the messages from the host that are meant for the system and
+/// passes the rest on to [`RX_CHANNEL`].  A task of its own so that the host
+/// is answered whether an app, the launcher or a dialog is running.
+#[embassy_executor::task]
+pub async fn answer_host() {
+    let mut rtc = rtc::Shared;
+    loop {
+        let message = FROM_HOST.receive().await;
+        match shared::system::answer(&message, &mut rtc) {
+            Some(reply) => CDC_TX_CHANNEL.send(reply).await,
+            None => {
+                if with_timeout(RX_TIMEOUT, RX_CHANNEL.send(message))
+                    .await
+                    .is_err()
+                {
+                    log::warn!("Nobody is reading, dropped message");
+                }
+            }
+        }
+    }
+}
+
+async fn receive<'d, D: embassy_usb::driver::Driver<'d>>(
+    receiver: &mut cdc_acm::Receiver<'d, D>,
+    decoder: &mut Decoder,
+) -> Result<(), Disconnected> {
+    let mut buf = [0; 64];
+    loop {
+        let n = receiver.read_packet(&mut buf).await?;
+        for byte in &buf[..n] {
+            match decoder.push(*byte) {
+                Some(Ok(message)) => FROM_HOST.send(message).await,
+                Some(Err(e)) => log::warn!("Dropped frame: {:?}", e),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Writes messages from [`CDC_TX_CHANNEL`] until disconnected.  A message
+/// interrupted by a disconnect is kept in `pending` and sent in full on
+/// reconnect.
+async fn transmit<'d, D: embassy_usb::driver::Driver<'d>>(
+    sender: &mut cdc_acm::Sender<'d, D>,
+    pending: &mut Option<Message>,
+) -> Result<(), Disconnected> {
+    let mut frame = [0; MAX_FRAME];
+    loop {
+        let message = match pending.take() {
+            Some(message) => message,
+            None => CDC_TX_CHANNEL.receive().await,
+        };
+        let length = match message.encode(&mut frame) {
+            Ok(length) => length,
+            Err(e) => {
+                log::warn!("Failed to encode message: {:?}", e);
+                continue;
+            }
+        };
+        *pending = Some(message);
+        for packet in frame[..length].chunks(64) {
+            sender.write_packet(packet).await?;
+        }
+        // a full final packet needs a zero length one to end the transfer
+        if length % 64 == 0 {
+            sender.write_packet(&[]).await?;
+        }
+        *pending = None;
+    }
 }
 
 struct Disconnected {}
@@ -1,23 +1,49 @@
 use embassy_rp::{
-    gpio::{Level, Output},
-    peripherals::PIN_2,
+    peripherals::{PIN_2, PWM_SLICE1},
+    pwm::{Config, Pwm, SetDutyCycle},
 };
 use shared::VibrationMotor;
 
-pub struct Motor<'a>(Output<'a>);
+pub struct Motor<'a> {
+    pwm: Pwm<'a>,
+    intensity: u8,
+    running: bool,
+}
 
 impl Motor<'_> {
-    pub fn new(pin: PIN_2) -> Self {
-        Self(Output::new(pin, Level::Low))
+    pub fn new(slice: PWM_SLICE1, pin: PIN_2) -> Self {
+        let mut pwm = Pwm::new_output_a(slice, pin, Config::default());
+        pwm.set_duty_cycle_fully_off().unwrap();
+        Self {
+            pwm,
+            intensity: 100,
+            running: false,
+        }
+    }
+
+    fn update(&mut self) {
+        let duty = if self.running {
+            self.intensity.min(100)
+        } else {
+            0
+        };
+        self.pwm.set_duty_cycle_percent(duty).unwrap();
     }
 }
 
 impl VibrationMotor for Motor<'_> {
     fn start(&mut self) {
-        self.0.set_high();
+        self.running = true;
+        self.update();
     }
 
     fn stop(&mut self) {
-        self.0.set_low();
+        self.running = false;
+        self.update();
+    }
+
+    fn set_intensity(&mut self, intensity: u8) {
+        self.intensity = intensity;
+        self.update();
     }
 }
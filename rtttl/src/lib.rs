@@ -2,7 +2,7 @@
 
 pub mod note;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Song<'a> {
     title: &'a str,
     duration: u32,
@@ -30,8 +30,8 @@ impl<'a> Song<'a> {
                 Some(("o", o)) | Some(("O", o)) => octave = o.parse().unwrap(),
                 Some(("d", d)) | Some(("D", d)) => duration = d.parse().unwrap(),
                 Some(("b", b)) | Some(("B", b)) => beats_per_minute = b.parse().unwrap(),
-                Some((k, v)) => panic!("panic {} {}", k, v),
-                None => panic!("panic"),
+                // anything else has no bearing on how the notes sound
+                Some(_) | None => {}
             }
         }
 
@@ -45,9 +45,19 @@ impl<'a> Song<'a> {
         }
     }
 
+    pub fn title(&self) -> &'a str {
+        self.title
+    }
+
+    pub fn beats_per_minute(&self) -> u32 {
+        self.beats_per_minute
+    }
+
     pub fn note_at(&mut self, time_ms: u32) -> Option<note::Note> {
         let note = note::Note::new(self.notes.peek().unwrap(), self.octave, self.duration);
-        self.time += note.duration(self.beats_per_minute);
+        self.time = self
+            .time
+            .saturating_add(note.duration(self.beats_per_minute));
         if self.time > time_ms {
             return Some(note);
         }
@@ -56,6 +66,16 @@ impl<'a> Song<'a> {
     }
 }
 
+/// The notes still to be played, in order.
+impl Iterator for Song<'_> {
+    type Item = note::Note;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.notes.by_ref().find(|text| !text.trim().is_empty())?;
+        Some(note::Note::new(text, self.octave, self.duration))
+    }
+}
+
 // TODO: expand tests
 #[cfg(test)]
 mod test {
@@ -94,4 +114,12 @@ mod test {
         assert_eq!(song.octave, 6);
         assert_eq!(song.beats_per_minute, 100);
     }
+
+    #[test]
+    fn test_notes() {
+        let song = Song::new(COUNTDOWN);
+
+        assert_eq!(song.clone().count(), 45);
+        assert_eq!(song.clone().nth(2), Some(note::Note::new("16b", 5, 4)));
+    }
 }
@@ -43,36 +43,45 @@ impl FromStr for NoteName {
     }
 }
 
+/// Duration (as a fraction of a whole note), name, octave and whether it is
+/// dotted.
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct Note(u32, NoteName, u32, bool);
 
 impl Note {
+    /// Parses `[duration]name[#][.][octave][.]`, eg. `8c#6` or `4e.`.
     pub fn new(text: &str, default_octave: u32, default_duration: u32) -> Self {
         let n = text.trim();
-        let mut find_name = n.match_indices(|c: char| !c.is_ascii_digit());
-        let (name_index, name) = find_name.next().unwrap();
+        let (duration, rest) = n.split_at(n.find(|c: char| !c.is_ascii_digit()).unwrap_or(n.len()));
+        let (name, octave) = rest.split_at(
+            rest.find(|c: char| c.is_ascii_digit() || c == '.')
+                .unwrap_or(rest.len()),
+        );
 
         Note(
-            n[name_index + name.len()..]
-                .parse()
-                .unwrap_or(default_duration),
-            name.parse().unwrap(),
-            n[..name_index].parse().unwrap_or(default_octave),
-            n.ends_with("."),
+            duration.parse().unwrap_or(default_duration),
+            name.parse().unwrap_or(NoteName::Pause),
+            octave.trim_matches('.').parse().unwrap_or(default_octave),
+            n.contains('.'),
         )
     }
 
+    /// In milliseconds.  Dotted notes last half as long again.
     pub fn duration(&self, beats_per_minute: u32) -> u32 {
+        // a whole note is four beats
+        let whole = 240_000_u32
+            .checked_div(beats_per_minute.saturating_mul(self.0))
+            .unwrap_or(0);
         if self.3 {
-            2 * 240 * 1000 / (3 * beats_per_minute * self.2)
+            whole.saturating_add(whole.checked_div(2).unwrap_or(0))
         } else {
-            240 * 1000 / (beats_per_minute * self.2)
+            whole
         }
     }
 
     // TODO: DRY
     pub fn frequency(&self) -> Option<Result<u32, ()>> {
-        match (self.0, self.1) {
+        match (self.2, self.1) {
             (_, NoteName::Pause) => None,
             (3, NoteName::C) => Some(Ok(130)),
             (3, NoteName::CSharp) => Some(Ok(138)),
@@ -122,7 +131,7 @@ impl Note {
             (6, NoteName::A) => Some(Ok(1760)),
             (6, NoteName::ASharp) => Some(Ok(1864)),
             (6, NoteName::B) => Some(Ok(1975)),
-            (o, n) => Some(Err(())),
+            (..) => Some(Err(())),
         }
     }
 }
@@ -134,5 +143,13 @@ mod test {
     #[test]
     fn test_note() {
         assert_eq!(Note::new("2a4", 5, 4), Note(2, NoteName::A, 4, false));
+        assert_eq!(
+            Note::new("16c#6", 5, 4),
+            Note(16, NoteName::CSharp, 6, false)
+        );
+        assert_eq!(Note::new("e.", 5, 4), Note(4, NoteName::E, 5, true));
+        assert_eq!(Note::new("8p", 5, 4).frequency(), None);
+        assert_eq!(Note::new("a4", 5, 4).frequency(), Some(Ok(440)));
+        assert_eq!(Note::new("4e.", 5, 4).duration(100), 900);
     }
 }
@@ -0,0 +1,160 @@
+#![no_std]
+
+use shared::{
+    Application, Board, Peripherals, SystemRequest, SystemResponse,
+    budget::Budget,
+    menu::{Item, Menu},
+    settings::{self, Settings as Preferences},
+    time_zone::ZONES,
+    widget,
+};
+
+const ITEMS: &[Item<'static, &'static str>] = &[
+    Item::Leaf {
+        label: "Contrast",
+        id: settings::CONTRAST,
+    },
+    Item::Leaf {
+        label: "Light timeout",
+        id: settings::BACKLIGHT_TIMEOUT,
+    },
+    Item::Leaf {
+        label: "Menu key tones",
+        id: settings::KEY_TONES,
+    },
+    Item::Leaf {
+        label: "Vibration",
+        id: settings::VIBRATION,
+    },
+    Item::Leaf {
+        label: "Volume",
+        id: settings::VOLUME,
+    },
+    Item::Leaf {
+        label: "Silent",
+        id: settings::SILENT,
+    },
+    Item::Leaf {
+        label: "Clock format",
+        id: settings::CLOCK_24H,
+    },
+    Item::Leaf {
+        label: "Time zone",
+        id: settings::TIME_ZONE,
+    },
+];
+
+const ON_OFF: &[(&str, i32)] = &[("On", 1), ("Off", 0)];
+const TIMEOUTS: &[(&str, i32)] = &[
+    ("5 seconds", 5),
+    ("15 seconds", 15),
+    ("30 seconds", 30),
+    ("1 minute", 60),
+    ("Never", 0),
+];
+const CLOCK_FORMATS: &[(&str, i32)] = &[("24 hour", 1), ("12 hour", 0)];
+
+/// Device preferences.  Each change is saved and applied by the system as
+/// soon as it is made.
+pub struct Settings {
+    menu: Menu<'static, &'static str>,
+    preferences: Preferences,
+    /// Asked to be saved, and kept in `preferences` once the system has.
+    saving: Option<(&'static str, i32)>,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(ITEMS).with_soft_keys(),
+            preferences: Preferences::DEFAULT,
+            saving: None,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Asks for a new value of `key`, starting from `current`.
+async fn ask(key: &'static str, current: i32, board: &mut impl Board) -> Option<i32> {
+    let Peripherals {
+        keypad, display, ..
+    } = board.peripherals();
+    let options = match key {
+        settings::CONTRAST => {
+            return widget::number("Contrast", 0..=100, current, keypad, display).await;
+        }
+        settings::VOLUME => {
+            return widget::number("Volume", 0..=100, current, keypad, display).await;
+        }
+        settings::TIME_ZONE => {
+            let current = usize::try_from(current).unwrap_or(0);
+            let zone = widget::choice("Time zone", ZONES, current, keypad, display).await?;
+            return ZONES
+                .iter()
+                .position(|(_, time_zone)| *time_zone == zone)
+                .and_then(|index| i32::try_from(index).ok());
+        }
+        settings::BACKLIGHT_TIMEOUT => TIMEOUTS,
+        settings::CLOCK_24H => CLOCK_FORMATS,
+        _ => ON_OFF,
+    };
+    let label = ITEMS
+        .iter()
+        .find(|item| matches!(item, Item::Leaf { id, .. } if *id == key))
+        .map_or(key, |item| item.label());
+    let index = options
+        .iter()
+        .position(|(_, value)| *value == current)
+        .unwrap_or(0);
+    widget::choice(label, options, index, keypad, display).await
+}
+
+impl Application for Settings {
+    async fn on_start(&mut self, board: &mut impl Board) {
+        self.preferences = Preferences::load(board.storage()).await;
+    }
+
+    async fn run(
+        &mut self,
+        board: &mut impl Board,
+        system_response: Option<SystemResponse>,
+    ) -> Option<SystemRequest> {
+        if let Some((key, value)) = self.saving.take() {
+            match system_response {
+                Some(SystemResponse::Done) => {
+                    self.preferences.set(key, value);
+                }
+                Some(SystemResponse::Failed(e)) => {
+                    log::warn!("setting not saved: {:?}", e);
+                    let message = heapless::String::try_from("Not saved").unwrap();
+                    return Some(SystemRequest::Notify(message));
+                }
+                _ => {}
+            }
+        }
+
+        let Peripherals {
+            keypad, display, ..
+        } = board.peripherals();
+        let key = self.menu.process(keypad, display).await?;
+
+        let current = self.preferences.get(key)?;
+        let value = ask(key, current, board).await?;
+        if value == current {
+            return None;
+        }
+        self.saving = Some((key, value));
+        Some(SystemRequest::SaveSetting { key, value })
+    }
+
+    // `run` waits for the user in a dialog, which would lose what they had
+    // picked if it were pre-empted
+    fn budget(&self) -> Budget {
+        Budget::INTERACTIVE
+    }
+}
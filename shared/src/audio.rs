@@ -0,0 +1,122 @@
+//! Tones and ringtones on any [`Buzzer`].
+//!
+//! Timing comes from `embassy_time` alone, so a song takes as long on the
+//! firmware as in the simulator.  Both play at whatever volume the buzzer is
+//! set to, and an app's buzzer already follows the volume and silent mode in
+//! the [`settings`](crate::settings).
+
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::Buzzer;
+
+/// Silence between notes, so that repeated notes are heard apart.  Taken out
+/// of the note before it rather than added, so the song keeps its tempo.
+const GAP: Duration = Duration::from_millis(10);
+
+/// How loud `volume` percent should sound, in thousandths of full scale.
+/// Follows a log curve over 40dB, which the ear hears as even steps, and is
+/// silent at 0 as if muted.  Shared by every target so that a volume sounds
+/// the same on each.
+pub fn amplitude(volume: u8) -> u16 {
+    // 10^((volume - 100) / 50), every 10%
+    const CURVE: [u16; 11] = [10, 16, 25, 40, 63, 100, 158, 251, 398, 631, 1000];
+    let volume = usize::from(volume.min(100));
+    if volume == 0 {
+        return 0;
+    }
+    let (low, high) = match (CURVE.get(volume / 10), CURVE.get(volume / 10 + 1)) {
+        (Some(low), Some(high)) => (*low, *high),
+        _ => return 1000,
+    };
+    low + (high - low) * (volume % 10) as u16 / 10
+}
+
+/// Mutes the buzzer when dropped, so that a cancelled tone never keeps
+/// sounding.
+struct Sounding<'a, B: Buzzer>(&'a mut B);
+
+impl<B: Buzzer> Drop for Sounding<'_, B> {
+    fn drop(&mut self) {
+        self.0.mute();
+    }
+}
+
+/// Sounds `frequency` (in Hz) for `duration`.
+pub async fn tone(buzzer: &mut impl Buzzer, frequency: u16, duration: Duration) {
+    let buzzer = Sounding(buzzer);
+    buzzer.0.set_frequency(frequency);
+    buzzer.0.unmute();
+    Timer::after(duration).await;
+}
+
+/// Plays `song` from the start.  Dropping the future stops it.
+pub async fn play(buzzer: &mut impl Buzzer, song: &rtttl::Song<'_>) {
+    let buzzer = Sounding(buzzer);
+    let beats_per_minute = song.beats_per_minute();
+    // each note is timed from the end of the last rather than from when it
+    // started, so that time spent switching notes does not add up
+    let mut end = Instant::now();
+    for note in song.clone() {
+        end += Duration::from_millis(note.duration(beats_per_minute).into());
+        match note.frequency() {
+            Some(Ok(frequency)) => {
+                buzzer
+                    .0
+                    .set_frequency(frequency.try_into().unwrap_or(u16::MAX));
+                buzzer.0.unmute();
+                Timer::at(end - GAP).await;
+                buzzer.0.mute();
+            }
+            Some(Err(())) => log::warn!("{:?} is out of range", note),
+            None => {}
+        }
+        Timer::at(end).await;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embassy_futures::select::select;
+
+    use super::*;
+    use crate::fake;
+
+    #[test]
+    fn test_amplitude() {
+        assert_eq!(amplitude(0), 0);
+        assert_eq!(amplitude(50), 100);
+        assert_eq!(amplitude(100), 1000);
+        assert_eq!(amplitude(200), 1000);
+        assert!((1..=100).all(|volume| amplitude(volume) >= amplitude(volume - 1)));
+    }
+
+    #[test]
+    fn test_tone() {
+        let mut buzzer = fake::Buzzer::default();
+
+        let started = Instant::now();
+        embassy_futures::block_on(tone(&mut buzzer, 440, Duration::from_millis(20)));
+
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert_eq!(buzzer.frequency, 440);
+        assert!(buzzer.muted);
+    }
+
+    #[test]
+    fn test_play() {
+        let mut buzzer = fake::Buzzer::default();
+        // four sixteenths at 600 bpm, 25ms each
+        let song = rtttl::Song::new("Test:d=16,o=5,b=600:a,p,c6,e");
+
+        let started = Instant::now();
+        embassy_futures::block_on(play(&mut buzzer, &song));
+
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert_eq!(buzzer.frequency, 659);
+        assert!(buzzer.muted);
+
+        embassy_futures::block_on(select(play(&mut buzzer, &song), Timer::after_millis(10)));
+        assert_eq!(buzzer.frequency, 880);
+        assert!(buzzer.muted, "dropping the song should mute the buzzer");
+    }
+}
@@ -0,0 +1,303 @@
+//! System managed backlight.  Any key lights it, and it goes off again once
+//! no key has been pressed for the timeout in the [`settings`](crate::settings).
+//! Apps can hold it on through their [`Backlight`], eg. for as long as a game
+//! is being played.
+
+use core::{
+    future::poll_fn,
+    sync::atomic::{AtomicBool, AtomicU8, AtomicU16, AtomicU32, Ordering},
+    task::Poll,
+};
+
+use embassy_futures::select::select;
+use embassy_sync::waitqueue::AtomicWaker;
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::{Backlight, Keypad, TimedKeyEvent, settings::Settings, system::System};
+
+/// When the backlight should be on, shared between the keypad that wakes it,
+/// the app that holds it and the system that switches it.  Atomics for the
+/// same reason as the [`Keyguard`](crate::keyguard::Keyguard)'s.
+pub struct Timeout {
+    /// Seconds, or 0 to never go off on its own.
+    seconds: AtomicU16,
+    /// When a key was last pressed, in milliseconds.  Wraps after 49 days,
+    /// which only matters while `asleep` is false.
+    woken_at: AtomicU32,
+    asleep: AtomicBool,
+    held: AtomicBool,
+    /// Percent.
+    brightness: AtomicU8,
+    /// Set on any change, for [`Timeout::drive`] to pick up.
+    changed: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Default for Timeout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timeout {
+    pub const fn new() -> Self {
+        Self {
+            seconds: AtomicU16::new(Settings::DEFAULT.backlight_timeout),
+            woken_at: AtomicU32::new(0),
+            asleep: AtomicBool::new(true),
+            held: AtomicBool::new(false),
+            brightness: AtomicU8::new(100),
+            changed: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    fn changed(&self) {
+        self.changed.store(true, Ordering::Relaxed);
+        self.waker.wake();
+    }
+
+    pub fn set_timeout(&self, seconds: u16) {
+        self.seconds.store(seconds, Ordering::Relaxed);
+        self.changed();
+    }
+
+    /// Lights the backlight for the timeout from `at`.
+    pub fn wake(&self, at: Instant) {
+        self.woken_at
+            .store(at.as_millis() as u32, Ordering::Relaxed);
+        self.asleep.store(false, Ordering::Relaxed);
+        self.changed();
+    }
+
+    /// Switches the backlight off until the next [`Timeout::wake`].
+    pub fn sleep(&self) {
+        self.asleep.store(true, Ordering::Relaxed);
+        self.changed();
+    }
+
+    /// Keeps the backlight on regardless of the timeout while `held`.
+    pub fn hold(&self, held: bool) {
+        self.held.store(held, Ordering::Relaxed);
+        self.changed();
+    }
+
+    /// Percent, for whenever it is lit.
+    pub fn set_brightness(&self, brightness: u8) {
+        self.brightness.store(brightness, Ordering::Relaxed);
+        self.changed();
+    }
+
+    /// Undoes any hold or dimming left behind by an app.
+    pub fn release(&self) {
+        self.hold(false);
+        self.set_brightness(100);
+    }
+
+    pub fn is_lit(&self, now: Instant) -> bool {
+        self.held.load(Ordering::Relaxed)
+            || (!self.asleep.load(Ordering::Relaxed) && self.off_at(now).is_none_or(|at| now < at))
+    }
+
+    /// When the backlight goes off if no key is pressed before then, or
+    /// `None` if it does not go off on its own.
+    pub fn off_at(&self, now: Instant) -> Option<Instant> {
+        let seconds = self.seconds.load(Ordering::Relaxed);
+        if seconds == 0 || self.held.load(Ordering::Relaxed) {
+            return None;
+        }
+        let awake = (now.as_millis() as u32).wrapping_sub(self.woken_at.load(Ordering::Relaxed));
+        let timeout = Duration::from_secs(seconds.into());
+        let left = timeout
+            .checked_sub(Duration::from_millis(awake.into()))
+            .unwrap_or(Duration::from_ticks(0));
+        Some(now + left)
+    }
+
+    /// Switches `backlight` to match, for as long as it is polled.  Only one
+    /// drive at a time is woken by changes.
+    pub async fn drive(&self, backlight: &mut impl Backlight) -> ! {
+        loop {
+            self.changed.store(false, Ordering::Relaxed);
+            let now = Instant::now();
+            if self.is_lit(now) {
+                backlight.set_brightness(self.brightness.load(Ordering::Relaxed));
+                backlight.on();
+            } else {
+                // so that a wrapped `woken_at` never lights it again
+                self.asleep.store(true, Ordering::Relaxed);
+                backlight.off();
+            }
+
+            let off_at = self.off_at(now).filter(|_| self.is_lit(now));
+            let changed = poll_fn(|cx| {
+                self.waker.register(cx.waker());
+                match self.changed.load(Ordering::Relaxed) {
+                    true => Poll::Ready(()),
+                    false => Poll::Pending,
+                }
+            });
+            match off_at {
+                Some(at) => {
+                    select(Timer::at(at), changed).await;
+                }
+                None => changed.await,
+            }
+        }
+    }
+}
+
+/// Picks up after [`keyguard::show`](crate::keyguard::show), which lights
+/// the backlight itself: it stays off while the keypad is locked, and is
+/// lit as if a key had just been pressed once it is unlocked.
+pub fn after_notice(system: &mut impl System) {
+    if system.keyguard().is_locked() {
+        system.backlight().sleep();
+    } else {
+        system.backlight().wake(Instant::now());
+    }
+}
+
+/// A keypad whose events wake the backlight.
+pub struct Waking<'a, K> {
+    keypad: K,
+    timeout: &'a Timeout,
+}
+
+impl<'a, K> Waking<'a, K> {
+    pub fn new(keypad: K, timeout: &'a Timeout) -> Self {
+        Self { keypad, timeout }
+    }
+}
+
+impl<K: Keypad> Keypad for Waking<'_, K> {
+    async fn event(&mut self) -> crate::KeyEvent {
+        self.timed_event().await.event
+    }
+
+    async fn timed_event(&mut self) -> TimedKeyEvent {
+        let event = self.keypad.timed_event().await;
+        self.timeout.wake(event.at);
+        event
+    }
+}
+
+/// The backlight as an app sees it: `on` holds it on until `off`, which
+/// switches it off until the next key, and the brightness stays until the
+/// app exits.
+pub struct Held<'a>(pub(crate) &'a Timeout);
+
+impl Backlight for Held<'_> {
+    fn on(&mut self) {
+        self.0.hold(true);
+    }
+
+    fn off(&mut self) {
+        self.0.hold(false);
+        self.0.sleep();
+    }
+
+    fn set_brightness(&mut self, brightness: u8) {
+        self.0.set_brightness(brightness);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::{
+        pin::pin,
+        task::{Context, RawWaker, RawWakerVTable, Waker},
+    };
+
+    use super::*;
+    use crate::{Key, KeyEvent, fake};
+
+    fn at(seconds: u64) -> Instant {
+        Instant::from_secs(seconds)
+    }
+
+    #[test]
+    fn test_timeout() {
+        let timeout = Timeout::new();
+        timeout.set_timeout(10);
+        assert!(!timeout.is_lit(at(0)));
+
+        timeout.wake(at(100));
+        assert!(timeout.is_lit(at(105)));
+        assert_eq!(timeout.off_at(at(105)), Some(at(110)));
+        assert!(!timeout.is_lit(at(110)));
+
+        timeout.hold(true);
+        assert!(timeout.is_lit(at(200)));
+        assert_eq!(timeout.off_at(at(200)), None);
+        Held(&timeout).off();
+        assert!(!timeout.is_lit(at(200)));
+
+        timeout.set_timeout(0);
+        timeout.wake(at(300));
+        assert!(timeout.is_lit(at(10_000)));
+    }
+
+    #[test]
+    fn test_drive() {
+        let timeout = Timeout::new();
+        let mut backlight = fake::Backlight::default();
+        Held(&timeout).set_brightness(30);
+        timeout.wake(Instant::now());
+
+        embassy_futures::block_on(select(timeout.drive(&mut backlight), async {}));
+
+        assert!(backlight.on);
+        assert_eq!(backlight.brightness, 30);
+    }
+
+    static WOKEN: AtomicBool = AtomicBool::new(false);
+
+    /// Records being woken, as `block_on` polls in a loop regardless.
+    fn recording_waker() -> Waker {
+        const VTABLE: RawWakerVTable = RawWakerVTable::new(
+            |_| RawWaker::new(core::ptr::null(), &VTABLE),
+            |_| WOKEN.store(true, Ordering::Relaxed),
+            |_| WOKEN.store(true, Ordering::Relaxed),
+            |_| {},
+        );
+        // SAFETY: the vtable never touches the data pointer
+        unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) }
+    }
+
+    #[test]
+    fn test_drive_woken() {
+        let timeout = Timeout::new();
+        timeout.set_timeout(0);
+        let mut backlight = fake::Backlight::default();
+        let waker = recording_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        {
+            let mut drive = pin!(timeout.drive(&mut backlight));
+            assert!(drive.as_mut().poll(&mut cx).is_pending());
+            assert!(!WOKEN.load(Ordering::Relaxed));
+
+            // eg. an app holding it on and then waiting for a key
+            Held(&timeout).on();
+            assert!(
+                WOKEN.load(Ordering::Relaxed),
+                "a hold should wake the drive"
+            );
+            assert!(drive.as_mut().poll(&mut cx).is_pending());
+        }
+        assert!(backlight.on);
+    }
+
+    #[test]
+    fn test_waking() {
+        let timeout = Timeout::new();
+        let mut keypad = fake::Keypad::default();
+        keypad.push(KeyEvent::Down(Key::Five));
+
+        let event = embassy_futures::block_on(Waking::new(keypad, &timeout).event());
+
+        assert_eq!(event, KeyEvent::Down(Key::Five));
+        assert!(timeout.is_lit(Instant::now()));
+    }
+}
@@ -0,0 +1,183 @@
+//! Time budgets for [`Application::run`](crate::Application::run) and the
+//! measurements [`run_app`](crate::run_app) takes while enforcing them.
+
+use embassy_time::{Duration, Instant, TimeoutError};
+
+/// How long a single `run` may take.
+///
+/// Going over `soft` is logged.  Going over `hard` cancels the `run` future
+/// (pre-empting the app), suspends the app and sends it
+/// [`SystemResponse::Preempted`](crate::SystemResponse::Preempted) on the
+/// next call.  Work that takes longer than `soft` should be split across
+/// several calls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Budget {
+    pub soft: Duration,
+    pub hard: Duration,
+}
+
+impl Budget {
+    pub const DEFAULT: Self = Self {
+        soft: Duration::from_millis(750),
+        hard: Duration::from_millis(1500),
+    };
+
+    /// For apps whose `run` waits for the user, eg. for a key or in a
+    /// dialog, which takes as long as they like.  Waiting is not work to
+    /// split up, so there is no soft budget to go over.
+    pub const INTERACTIVE: Self = Self {
+        soft: Duration::from_secs(600),
+        hard: Duration::from_secs(600),
+    };
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// What happened to a single `run`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Completed,
+    OverSoftBudget,
+    Preempted,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timing {
+    pub runs: u32,
+    pub soft_overruns: u32,
+    pub preemptions: u32,
+    /// How long the most recent `run` took.
+    pub last_run: Duration,
+    pub longest_run: Duration,
+    /// Time spent in the system between the end of one `run` and the start of
+    /// the next.
+    pub last_gap: Duration,
+    pub longest_gap: Duration,
+    last_finished: Option<Instant>,
+}
+
+impl Timing {
+    /// Records a `run` that gave `result`, which is an error if it was
+    /// cancelled for going over the hard budget.
+    pub fn record<T>(
+        &mut self,
+        budget: &Budget,
+        started: Instant,
+        finished: Instant,
+        result: &Result<T, TimeoutError>,
+    ) -> Outcome {
+        let run = finished - started;
+
+        self.runs += 1;
+        self.last_run = run;
+        self.longest_run = self.longest_run.max(run);
+        if let Some(last_finished) = self.last_finished {
+            self.last_gap = started - last_finished;
+            self.longest_gap = self.longest_gap.max(self.last_gap);
+        }
+        self.last_finished = Some(finished);
+
+        if result.is_err() {
+            self.preemptions += 1;
+            Outcome::Preempted
+        } else if run > budget.soft {
+            self.soft_overruns += 1;
+            Outcome::OverSoftBudget
+        } else {
+            Outcome::Completed
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const BUDGET: Budget = Budget {
+        soft: Duration::from_millis(10),
+        hard: Duration::from_millis(20),
+    };
+
+    #[test]
+    fn test_record() {
+        let mut timing = Timing::default();
+
+        assert_eq!(
+            timing.record(
+                &BUDGET,
+                Instant::from_millis(0),
+                Instant::from_millis(5),
+                &Ok(())
+            ),
+            Outcome::Completed
+        );
+        assert_eq!(timing.last_gap, Duration::from_millis(0));
+
+        assert_eq!(
+            timing.record(
+                &BUDGET,
+                Instant::from_millis(8),
+                Instant::from_millis(20),
+                &Ok(())
+            ),
+            Outcome::OverSoftBudget
+        );
+        assert_eq!(timing.last_gap, Duration::from_millis(3));
+
+        assert_eq!(
+            timing.record(
+                &BUDGET,
+                Instant::from_millis(21),
+                Instant::from_millis(41),
+                &Err::<(), _>(TimeoutError)
+            ),
+            Outcome::Preempted
+        );
+        assert_eq!(
+            timing.record(
+                &BUDGET,
+                Instant::from_millis(50),
+                Instant::from_millis(51),
+                &Ok(())
+            ),
+            Outcome::Completed
+        );
+
+        assert_eq!(timing.runs, 4);
+        assert_eq!(timing.soft_overruns, 1);
+        assert_eq!(timing.preemptions, 1);
+        assert_eq!(timing.last_run, Duration::from_millis(1));
+        assert_eq!(timing.longest_run, Duration::from_millis(20));
+        assert_eq!(timing.last_gap, Duration::from_millis(9));
+        assert_eq!(timing.longest_gap, Duration::from_millis(9));
+    }
+
+    #[test]
+    fn test_record_takes_result() {
+        let mut timing = Timing::default();
+
+        // finishing right on the hard budget is not being cancelled
+        assert_eq!(
+            timing.record(
+                &BUDGET,
+                Instant::from_millis(0),
+                Instant::from_millis(20),
+                &Ok(())
+            ),
+            Outcome::OverSoftBudget
+        );
+        // nor is waiting on the user for a minute
+        let interactive = Budget::INTERACTIVE;
+        let finished = Instant::from_secs(61);
+        assert_eq!(
+            timing.record(&interactive, Instant::from_secs(1), finished, &Ok(())),
+            Outcome::Completed
+        );
+        assert_eq!(timing.preemptions, 0);
+        assert_eq!(timing.soft_overruns, 1);
+    }
+}
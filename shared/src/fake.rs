@@ -0,0 +1,339 @@
+//! In-memory [`Board`](crate::Board) for exercising applications and system
+//! code on the host.
+
+use core::convert::Infallible;
+
+use embassy_time::Instant;
+use embedded_graphics::{
+    Pixel,
+    pixelcolor::BinaryColor,
+    prelude::{DrawTarget, OriginDimensions, Point, Size},
+};
+
+use super::{
+    KeyEvent, RequestError, UsbRx, UsbTx, backlight::Timeout, keyguard::Keyguard,
+    settings::Settings, storage,
+};
+
+pub const WIDTH: usize = 84;
+pub const HEIGHT: usize = 48;
+
+#[derive(Default)]
+pub struct VibrationMotor {
+    pub running: bool,
+    pub intensity: u8,
+    /// Every start (`true`) and stop (`false`) and when it happened, up to
+    /// the first 32.
+    pub history: heapless::Vec<(bool, Instant), 32>,
+}
+
+impl super::VibrationMotor for VibrationMotor {
+    fn start(&mut self) {
+        self.running = true;
+        let _ = self.history.push((true, Instant::now()));
+    }
+
+    fn stop(&mut self) {
+        self.running = false;
+        let _ = self.history.push((false, Instant::now()));
+    }
+
+    fn set_intensity(&mut self, intensity: u8) {
+        self.intensity = intensity;
+    }
+}
+
+#[derive(Default)]
+pub struct Buzzer {
+    pub frequency: u16,
+    pub volume: u8,
+    pub muted: bool,
+}
+
+impl super::Buzzer for Buzzer {
+    fn set_frequency(&mut self, frequency: u16) {
+        self.frequency = frequency;
+    }
+
+    fn set_volume(&mut self, volume: u8) {
+        self.volume = volume;
+    }
+
+    fn mute(&mut self) {
+        self.muted = true;
+    }
+
+    fn unmute(&mut self) {
+        self.muted = false;
+    }
+}
+
+/// An 84x48 framebuffer, the same size as the PCD8544.
+pub struct Display {
+    pixels: [[BinaryColor; WIDTH]; HEIGHT],
+    pub contrast: u8,
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Self {
+            pixels: [[BinaryColor::Off; WIDTH]; HEIGHT],
+            contrast: Settings::DEFAULT.contrast,
+        }
+    }
+}
+
+impl Display {
+    pub fn pixel(&self, point: Point) -> Option<BinaryColor> {
+        let x: usize = point.x.try_into().ok()?;
+        let y: usize = point.y.try_into().ok()?;
+        self.pixels.get(y)?.get(x).copied()
+    }
+}
+
+impl super::Display for Display {
+    fn set_contrast(&mut self, contrast: u8) {
+        self.contrast = contrast;
+    }
+}
+
+impl DrawTarget for Display {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I: IntoIterator<Item = Pixel<Self::Color>>>(
+        &mut self,
+        pixels: I,
+    ) -> Result<(), Self::Error> {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x), Ok(y)) = (usize::try_from(point.x), usize::try_from(point.y))
+                && let Some(pixel) = self.pixels.get_mut(y).and_then(|row| row.get_mut(x))
+            {
+                *pixel = color;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl OriginDimensions for Display {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+/// Replays queued key events.  Once the queue is empty, `event` never
+/// resolves, just like a real keypad nobody is touching.
+#[derive(Default)]
+pub struct Keypad(pub heapless::Deque<KeyEvent, 32>);
+
+impl Keypad {
+    pub fn push(&mut self, event: KeyEvent) {
+        self.0.push_back(event).unwrap();
+    }
+}
+
+impl super::Keypad for Keypad {
+    async fn event(&mut self) -> KeyEvent {
+        match self.0.pop_front() {
+            Some(event) => event,
+            None => core::future::pending().await,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Rtc(pub i64);
+
+impl super::Rtc for Rtc {
+    fn timestamp(&mut self) -> i64 {
+        self.0
+    }
+
+    fn set_timestamp(&mut self, timestamp: i64) -> Result<(), super::RtcError> {
+        self.0 = timestamp;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct Backlight {
+    pub on: bool,
+    pub brightness: u8,
+}
+
+impl super::Backlight for Backlight {
+    fn on(&mut self) {
+        self.on = true;
+    }
+
+    fn off(&mut self) {
+        self.on = false;
+    }
+
+    fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+    }
+}
+
+/// Keeps records in RAM, forgetting them when dropped.
+#[derive(Default)]
+pub struct Storage(
+    pub  heapless::Vec<
+        (
+            heapless::String<{ storage::MAX_KEY }>,
+            heapless::Vec<u8, { storage::MAX_VALUE }>,
+        ),
+        16,
+    >,
+);
+
+impl Storage {
+    fn position(&self, key: &str) -> Option<usize> {
+        self.0.iter().position(|(k, _)| k == key)
+    }
+}
+
+impl super::Storage for Storage {
+    async fn read(
+        &mut self,
+        key: &str,
+        buffer: &mut [u8],
+    ) -> Result<Option<usize>, storage::Error> {
+        storage::check_key(key)?;
+        let Some(index) = self.position(key) else {
+            return Ok(None);
+        };
+        let value = &self.0[index].1;
+        buffer
+            .get_mut(..value.len())
+            .ok_or(storage::Error::TooLarge)?
+            .copy_from_slice(value);
+        Ok(Some(value.len()))
+    }
+
+    async fn write(&mut self, key: &str, value: &[u8]) -> Result<(), storage::Error> {
+        storage::check_key(key)?;
+        let value = heapless::Vec::from_slice(value).map_err(|_| storage::Error::TooLarge)?;
+        match self.position(key) {
+            Some(index) => self.0[index].1 = value,
+            None => {
+                let key =
+                    heapless::String::try_from(key).map_err(|_| storage::Error::InvalidKey)?;
+                self.0
+                    .push((key, value))
+                    .map_err(|_| storage::Error::Full)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), storage::Error> {
+        storage::check_key(key)?;
+        if let Some(index) = self.position(key) {
+            self.0.swap_remove(index);
+        }
+        Ok(())
+    }
+}
+
+/// Reports a press once `was_pressed` has been polled a set number of times.
+#[derive(Default)]
+pub struct PowerButton(Option<usize>);
+
+impl PowerButton {
+    pub fn press_after(&mut self, polls: usize) {
+        self.0 = Some(polls);
+    }
+}
+
+impl super::PowerButton for PowerButton {
+    async fn was_pressed(&mut self) -> bool {
+        match self.0 {
+            Some(0) => true,
+            Some(ref mut remaining) => {
+                *remaining -= 1;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Board {
+    pub vibration_motor: VibrationMotor,
+    pub buzzer: Buzzer,
+    pub display: Display,
+    pub keypad: Keypad,
+    pub rtc: Rtc,
+    pub backlight: Backlight,
+    pub storage: Storage,
+    pub power_button: PowerButton,
+}
+
+impl super::Board for Board {
+    type VibrationMotor = VibrationMotor;
+    type Buzzer = Buzzer;
+    type Display = Display;
+    type Keypad = Keypad;
+    type Rtc = Rtc;
+    type Backlight = Backlight;
+    type Storage = Storage;
+    type PowerButton = PowerButton;
+
+    fn peripherals(&mut self) -> super::Peripherals<'_, Self> {
+        super::Peripherals {
+            vibration_motor: &mut self.vibration_motor,
+            buzzer: &mut self.buzzer,
+            display: &mut self.display,
+            keypad: &mut self.keypad,
+            rtc: &mut self.rtc,
+            backlight: &mut self.backlight,
+            storage: &mut self.storage,
+            power_button: &mut self.power_button,
+        }
+    }
+}
+
+/// Records everything sent over USB and replays queued received buffers.
+#[derive(Default)]
+pub struct System {
+    pub sent: heapless::Vec<UsbTx, 16>,
+    pub received: heapless::Deque<UsbRx, 4>,
+    pub keyguard: Keyguard,
+    pub usb_connected: bool,
+    pub battery: Option<u8>,
+    pub settings: Settings,
+    pub backlight: Timeout,
+}
+
+impl super::system::System for System {
+    fn keyguard(&self) -> &Keyguard {
+        &self.keyguard
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    fn backlight(&self) -> &Timeout {
+        &self.backlight
+    }
+
+    fn usb_connected(&self) -> bool {
+        self.usb_connected
+    }
+
+    fn battery(&mut self) -> Option<u8> {
+        self.battery
+    }
+
+    async fn usb_tx(&mut self, tx: UsbTx) -> Result<(), RequestError> {
+        self.sent.push(tx).map_err(|_| RequestError::Unavailable)
+    }
+
+    fn usb_rx(&mut self) -> Option<UsbRx> {
+        self.received.pop_front()
+    }
+}
@@ -0,0 +1,443 @@
+//! Nokia style keyguard.  Select followed by `*` locks the keypad and the
+//! same again unlocks it.  While locked, key events never reach the app and
+//! any press lights the backlight for a moment instead.  The Select is held
+//! back until it is clear whether `*` follows, so locking never also selects
+//! whatever is on screen.
+
+use core::{
+    future::poll_fn,
+    sync::atomic::{AtomicBool, AtomicU8, AtomicU32, Ordering},
+    task::Poll,
+};
+
+use embassy_futures::select::{Either, select};
+use embassy_time::{Duration, Instant, with_timeout};
+
+use crate::{
+    Backlight, Board, Key, KeyEvent, Keypad, Peripherals, TimedKeyEvent,
+    system::{clear_notification, draw_notification},
+};
+
+/// How soon after Select `*` has to be pressed.
+pub const WINDOW: Duration = Duration::from_millis(1500);
+/// How long the backlight stays on after a press while locked, or after a
+/// notice.
+pub const LIT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Notice {
+    Locked,
+    Unlocked,
+    /// A key was pressed while locked.
+    Pressed,
+}
+
+impl Notice {
+    fn message(self) -> Option<&'static str> {
+        match self {
+            Notice::Locked => Some("Keys locked"),
+            Notice::Unlocked => Some("Keys unlocked"),
+            Notice::Pressed => None,
+        }
+    }
+}
+
+const NO_NOTICE: u8 = 0;
+
+/// Events to pass on after one from the keypad: any Select held back, then
+/// the event itself.
+pub type Passed = heapless::Vec<TimedKeyEvent, 3>;
+
+/// Milliseconds, wrapping after 49 days, which only matters for the window.
+fn millis(at: Instant) -> u32 {
+    at.as_millis() as u32
+}
+
+/// Lock state shared between the [`Guarded`] keypad an app reads from and
+/// the system around it.  Atomics rather than cells so that keypad futures
+/// borrowing it stay `Send`.
+#[derive(Default)]
+pub struct Keyguard {
+    locked: AtomicBool,
+    /// Whether a Select is being held back, waiting for `*`.
+    armed: AtomicBool,
+    /// When Select was pressed, in milliseconds.  Only meaningful while
+    /// `armed`.
+    select_at: AtomicU32,
+    /// Whether the held back Select has been released, and when.
+    released: AtomicBool,
+    released_at: AtomicU32,
+    notice: AtomicU8,
+}
+
+impl Keyguard {
+    pub const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            armed: AtomicBool::new(false),
+            select_at: AtomicU32::new(0),
+            released: AtomicBool::new(false),
+            released_at: AtomicU32::new(0),
+            notice: AtomicU8::new(NO_NOTICE),
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+
+    fn post(&self, notice: Notice) {
+        let code = match notice {
+            Notice::Locked => 1,
+            Notice::Unlocked => 2,
+            Notice::Pressed => 3,
+        };
+        // a press never hides a lock or unlock that has not been shown yet
+        if notice != Notice::Pressed || self.notice.load(Ordering::Relaxed) == NO_NOTICE {
+            self.notice.store(code, Ordering::Relaxed);
+        }
+    }
+
+    fn has_notice(&self) -> bool {
+        self.notice.load(Ordering::Relaxed) != NO_NOTICE
+    }
+
+    /// Takes the notice posted since last time, if any.
+    pub fn take_notice(&self) -> Option<Notice> {
+        match self.notice.swap(NO_NOTICE, Ordering::Relaxed) {
+            1 => Some(Notice::Locked),
+            2 => Some(Notice::Unlocked),
+            3 => Some(Notice::Pressed),
+            _ => None,
+        }
+    }
+
+    /// Resolves with the next notice.  Notices are only posted by a
+    /// [`Guarded`] keypad, so this must be polled alongside the future
+    /// reading from it, as it is never woken on its own.
+    pub async fn notice(&self) -> Notice {
+        poll_fn(|_| match self.take_notice() {
+            Some(notice) => Poll::Ready(notice),
+            None => Poll::Pending,
+        })
+        .await
+    }
+
+    /// Updates the lock state for `event`, returning the events to pass on
+    /// in its place.
+    pub fn filter(&self, event: &TimedKeyEvent) -> Passed {
+        let now = millis(event.at);
+        let armed = self.armed.load(Ordering::Relaxed);
+        match event.event {
+            KeyEvent::Down(Key::Asterisk)
+                if armed
+                    && u64::from(now.wrapping_sub(self.select_at.load(Ordering::Relaxed)))
+                        <= WINDOW.as_millis() =>
+            {
+                self.armed.store(false, Ordering::Relaxed);
+                let locked = !self.locked.fetch_xor(true, Ordering::Relaxed);
+                self.post(if locked {
+                    Notice::Locked
+                } else {
+                    Notice::Unlocked
+                });
+                return Passed::new();
+            }
+            KeyEvent::Up(Key::Select) if armed => {
+                self.released_at.store(now, Ordering::Relaxed);
+                self.released.store(true, Ordering::Relaxed);
+                return Passed::new();
+            }
+            _ => {}
+        }
+
+        // anything else means the Select was not the start of the sequence
+        let mut passed = self.release(event.at);
+        if matches!(event.event, KeyEvent::Down(_)) && self.is_locked() {
+            self.post(Notice::Pressed);
+        }
+        if event.event == KeyEvent::Down(Key::Select) {
+            self.select_at.store(now, Ordering::Relaxed);
+            self.released.store(false, Ordering::Relaxed);
+            self.armed.store(true, Ordering::Relaxed);
+        } else if !self.is_locked() {
+            let _ = passed.push(*event);
+        }
+        passed
+    }
+
+    /// How much longer `*` can follow the Select held back, if there is one.
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        if !self.armed.load(Ordering::Relaxed) {
+            return None;
+        }
+        let elapsed = elapsed(now, self.select_at.load(Ordering::Relaxed));
+        Some(
+            WINDOW
+                .checked_sub(elapsed)
+                .unwrap_or(Duration::from_ticks(0)),
+        )
+    }
+
+    /// Gives up waiting for `*`, returning the Select held back and its
+    /// release, if it was, to pass on at `now`.
+    pub fn release(&self, now: Instant) -> Passed {
+        let mut passed = Passed::new();
+        if !self.armed.swap(false, Ordering::Relaxed) || self.is_locked() {
+            return passed;
+        }
+        let select_at = now - elapsed(now, self.select_at.load(Ordering::Relaxed));
+        let _ = passed.push(TimedKeyEvent {
+            event: KeyEvent::Down(Key::Select),
+            at: select_at,
+        });
+        if self.released.load(Ordering::Relaxed) {
+            let released_at = now - elapsed(now, self.released_at.load(Ordering::Relaxed));
+            let _ = passed.push(TimedKeyEvent {
+                event: KeyEvent::Up(Key::Select),
+                at: released_at,
+            });
+        }
+        passed
+    }
+}
+
+/// How long before `now` a time stored by [`millis`] was, up to `now` itself.
+fn elapsed(now: Instant, then: u32) -> Duration {
+    let elapsed = Duration::from_millis(millis(now).wrapping_sub(then).into());
+    let since_boot = Duration::from_ticks(now.as_ticks());
+    elapsed.min(since_boot)
+}
+
+/// A keypad whose events go through a [`Keyguard`] first.  It stops reading
+/// keys once a notice is posted, leaving the rest for after the notice is
+/// shown, so it must be polled alongside [`Keyguard::notice`].
+pub struct Guarded<'a, K> {
+    keypad: &'a mut K,
+    keyguard: &'a Keyguard,
+    /// Passed on by the keyguard but not returned yet.
+    passed: heapless::Deque<TimedKeyEvent, 3>,
+}
+
+impl<'a, K> Guarded<'a, K> {
+    pub fn new(keypad: &'a mut K, keyguard: &'a Keyguard) -> Self {
+        Self {
+            keypad,
+            keyguard,
+            passed: heapless::Deque::new(),
+        }
+    }
+}
+
+impl<K: Keypad> Keypad for Guarded<'_, K> {
+    async fn event(&mut self) -> KeyEvent {
+        self.timed_event().await.event
+    }
+
+    async fn timed_event(&mut self) -> TimedKeyEvent {
+        loop {
+            if let Some(event) = self.passed.pop_front() {
+                return event;
+            }
+            if self.keyguard.has_notice() {
+                return core::future::pending().await;
+            }
+            // a Select held back is passed on once the window is up
+            let passed = match self.keyguard.remaining(Instant::now()) {
+                Some(remaining) => match with_timeout(remaining, self.keypad.timed_event()).await {
+                    Ok(event) => self.keyguard.filter(&event),
+                    Err(_) => self.keyguard.release(Instant::now()),
+                },
+                None => self.keyguard.filter(&self.keypad.timed_event().await),
+            };
+            for event in passed {
+                let _ = self.passed.push_back(event);
+            }
+        }
+    }
+}
+
+/// Shows `notice` with the backlight on, and keeps it on for as long as keys
+/// are pressed while locked.  Keys keep going through `keyguard` meanwhile,
+/// so the keypad can still be unlocked, and once it is any key dismisses the
+/// notice.
+pub async fn show(notice: Notice, board: &mut impl Board, keyguard: &Keyguard) {
+    let mut notice = notice;
+    loop {
+        let Peripherals {
+            display,
+            keypad,
+            backlight,
+            ..
+        } = board.peripherals();
+        backlight.on();
+        if let Some(message) = notice.message() {
+            draw_notification(message, display);
+        }
+
+        let next = with_timeout(
+            LIT,
+            select(Guarded::new(keypad, keyguard).event(), keyguard.notice()),
+        )
+        .await;
+        if notice.message().is_some() {
+            clear_notification(display);
+        }
+
+        match next {
+            Ok(Either::Second(next)) => notice = next,
+            _ => break,
+        }
+    }
+    board.backlight().off();
+}
+
+#[cfg(test)]
+mod test {
+    use embassy_time::Instant;
+
+    use super::*;
+    use crate::{
+        fake,
+        menu::{Item, Menu},
+    };
+
+    fn down(key: Key, at: u64) -> TimedKeyEvent {
+        TimedKeyEvent {
+            event: KeyEvent::Down(key),
+            at: Instant::from_millis(at),
+        }
+    }
+
+    fn events(passed: Passed) -> heapless::Vec<KeyEvent, 3> {
+        passed.iter().map(|passed| passed.event).collect()
+    }
+
+    #[test]
+    fn test_lock_and_unlock() {
+        let keyguard = Keyguard::new();
+
+        assert!(keyguard.filter(&down(Key::Select, 0)).is_empty());
+        assert!(keyguard.filter(&down(Key::Asterisk, 500)).is_empty());
+        assert!(keyguard.is_locked());
+        assert_eq!(keyguard.take_notice(), Some(Notice::Locked));
+
+        assert!(keyguard.filter(&down(Key::Five, 1000)).is_empty());
+        assert_eq!(keyguard.take_notice(), Some(Notice::Pressed));
+        assert!(keyguard.filter(&down(Key::Select, 2000)).is_empty());
+        assert!(keyguard.filter(&down(Key::Asterisk, 2100)).is_empty());
+        assert!(!keyguard.is_locked());
+        assert_eq!(keyguard.take_notice(), Some(Notice::Unlocked));
+
+        assert_eq!(
+            events(keyguard.filter(&down(Key::Asterisk, 2200))),
+            [KeyEvent::Down(Key::Asterisk)]
+        );
+    }
+
+    #[test]
+    fn test_window() {
+        let keyguard = Keyguard::new();
+
+        keyguard.filter(&down(Key::Select, 0));
+        assert_eq!(
+            events(keyguard.filter(&down(Key::Asterisk, 2000))),
+            [KeyEvent::Down(Key::Select), KeyEvent::Down(Key::Asterisk)],
+            "too late for `*`, so Select is passed on"
+        );
+        keyguard.filter(&down(Key::Select, 3000));
+        let up = TimedKeyEvent {
+            event: KeyEvent::Up(Key::Select),
+            at: Instant::from_millis(3050),
+        };
+        assert!(keyguard.filter(&up).is_empty());
+        let passed = keyguard.filter(&down(Key::Two, 3100));
+        assert_eq!(
+            events(passed.clone()),
+            [
+                KeyEvent::Down(Key::Select),
+                KeyEvent::Up(Key::Select),
+                KeyEvent::Down(Key::Two)
+            ]
+        );
+        assert_eq!(passed[0].at, Instant::from_millis(3000));
+        assert_eq!(keyguard.filter(&down(Key::Asterisk, 3200)).len(), 1);
+        assert!(!keyguard.is_locked());
+
+        keyguard.filter(&down(Key::Select, 4000));
+        assert_eq!(
+            keyguard.remaining(Instant::from_millis(4500)),
+            Some(WINDOW - Duration::from_millis(500))
+        );
+        assert_eq!(
+            events(keyguard.release(Instant::from_millis(6000))),
+            [KeyEvent::Down(Key::Select)]
+        );
+        assert_eq!(keyguard.remaining(Instant::from_millis(6000)), None);
+    }
+
+    #[test]
+    fn test_guarded() {
+        let keyguard = Keyguard::new();
+        let mut keypad = fake::Keypad::default();
+        for key in [Key::Select, Key::Asterisk, Key::One, Key::Two] {
+            keypad.push(KeyEvent::Down(key));
+        }
+        // the held back Select is never passed on
+        let mut next = || {
+            embassy_futures::block_on(select(
+                Guarded::new(&mut keypad, &keyguard).event(),
+                keyguard.notice(),
+            ))
+        };
+
+        assert!(matches!(next(), Either::Second(Notice::Locked)));
+        assert!(matches!(next(), Either::Second(Notice::Pressed)));
+        assert!(matches!(next(), Either::Second(Notice::Pressed)));
+    }
+
+    #[test]
+    fn test_show() {
+        let keyguard = Keyguard::new();
+        let mut board = fake::Board::default();
+        for key in [Key::Select, Key::Asterisk, Key::Five] {
+            board.keypad.push(KeyEvent::Down(key));
+        }
+        keyguard.locked.store(true, Ordering::Relaxed);
+
+        embassy_futures::block_on(show(Notice::Pressed, &mut board, &keyguard));
+
+        assert!(!keyguard.is_locked());
+        assert!(!board.backlight.on);
+        assert_eq!(
+            board.keypad.0.len(),
+            0,
+            "Five should have dismissed the notice"
+        );
+    }
+
+    #[test]
+    fn test_lock_from_menu() {
+        const ITEMS: &[Item<'static, u8>] = &[Item::Leaf {
+            label: "Clock",
+            id: 0,
+        }];
+        let keyguard = Keyguard::new();
+        let mut keypad = fake::Keypad::default();
+        let mut display = fake::Display::default();
+        let mut menu = Menu::new(ITEMS);
+        keypad.push(KeyEvent::Down(Key::Select));
+        keypad.push(KeyEvent::Up(Key::Select));
+        keypad.push(KeyEvent::Down(Key::Asterisk));
+
+        let result = embassy_futures::block_on(select(
+            menu.process(&mut Guarded::new(&mut keypad, &keyguard), &mut display),
+            keyguard.notice(),
+        ));
+
+        assert!(
+            matches!(result, Either::Second(Notice::Locked)),
+            "the menu should never see the Select"
+        );
+    }
+}
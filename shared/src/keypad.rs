@@ -0,0 +1,296 @@
+//! Long presses and auto-repeat on top of a keypad that only reports keys
+//! going up and down.
+
+use core::{
+    future::poll_fn,
+    sync::atomic::{AtomicBool, Ordering},
+    task::Poll,
+};
+
+use embassy_futures::select::{Either, select};
+use embassy_sync::waitqueue::AtomicWaker;
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::{Buzzer, Key, KeyEvent, Keypad, TimedKeyEvent, audio};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Thresholds {
+    /// How long a key is held before [`KeyEvent::LongPress`].
+    pub long_press: Duration,
+    /// How often [`KeyEvent::Repeat`] follows the long press while the key
+    /// is still held.
+    pub repeat: Duration,
+}
+
+impl Thresholds {
+    pub const DEFAULT: Self = Self {
+        long_press: Duration::from_millis(800),
+        repeat: Duration::from_millis(150),
+    };
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The key being held and when its next event is due.
+#[derive(Clone, Copy)]
+struct Held {
+    key: Key,
+    due: Instant,
+    long_pressed: bool,
+}
+
+/// Wraps a keypad so that holding a key sends [`KeyEvent::LongPress`] and
+/// then [`KeyEvent::Repeat`] until it is released.  Only the most recently
+/// pressed key is tracked.
+pub struct Gestures<K> {
+    keypad: K,
+    thresholds: Thresholds,
+    held: Option<Held>,
+}
+
+impl<K: Keypad> Gestures<K> {
+    pub fn new(keypad: K) -> Self {
+        Self::with_thresholds(keypad, Thresholds::DEFAULT)
+    }
+
+    pub fn with_thresholds(keypad: K, thresholds: Thresholds) -> Self {
+        Self {
+            keypad,
+            thresholds,
+            held: None,
+        }
+    }
+
+    pub fn thresholds(&self) -> Thresholds {
+        self.thresholds
+    }
+
+    /// Takes effect from the next key pressed.
+    pub fn set_thresholds(&mut self, thresholds: Thresholds) {
+        self.thresholds = thresholds;
+    }
+
+    fn track(&mut self, event: TimedKeyEvent) -> TimedKeyEvent {
+        match event.event {
+            KeyEvent::Down(key) => {
+                self.held = Some(Held {
+                    key,
+                    due: event.at + self.thresholds.long_press,
+                    long_pressed: false,
+                });
+            }
+            KeyEvent::Up(key) if self.held.is_some_and(|held| held.key == key) => {
+                self.held = None;
+            }
+            _ => {}
+        }
+        event
+    }
+}
+
+impl<K: Keypad> Keypad for Gestures<K> {
+    async fn event(&mut self) -> KeyEvent {
+        self.timed_event().await.event
+    }
+
+    async fn timed_event(&mut self) -> TimedKeyEvent {
+        let Some(held) = self.held else {
+            let event = self.keypad.timed_event().await;
+            return self.track(event);
+        };
+
+        match select(self.keypad.timed_event(), Timer::at(held.due)).await {
+            Either::First(event) => self.track(event),
+            Either::Second(()) => {
+                // from now rather than when it was due, so that a caller
+                // that is slow to ask does not get a burst of repeats
+                let now = Instant::now();
+                self.held = Some(Held {
+                    due: now + self.thresholds.repeat,
+                    long_pressed: true,
+                    ..held
+                });
+                let event = if held.long_pressed {
+                    KeyEvent::Repeat(held.key)
+                } else {
+                    KeyEvent::LongPress(held.key)
+                };
+                TimedKeyEvent { event, at: now }
+            }
+        }
+    }
+}
+
+/// Pitch and length of a key tone.
+const TONE_FREQUENCY: u16 = 2000;
+const TONE_LENGTH: Duration = Duration::from_millis(20);
+
+/// Key clicks asked for by a [`KeyTones`] keypad, and played by whatever
+/// holds the buzzer, so that the keypad need not hold it too.
+pub struct Clicks {
+    pending: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Default for Clicks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clicks {
+    pub const fn new() -> Self {
+        Self {
+            pending: AtomicBool::new(false),
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    fn request(&self) {
+        self.pending.store(true, Ordering::Relaxed);
+        self.waker.wake();
+    }
+
+    /// Plays each click on `buzzer` as it is asked for.  Never resolves, so
+    /// poll it alongside whatever reads the keypad.
+    pub async fn play(&self, buzzer: &mut impl Buzzer) -> ! {
+        loop {
+            poll_fn(|cx| {
+                self.waker.register(cx.waker());
+                if self.pending.swap(false, Ordering::Relaxed) {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            })
+            .await;
+            audio::tone(buzzer, TONE_FREQUENCY, TONE_LENGTH).await;
+        }
+    }
+}
+
+/// Asks for a click each time a key goes down, if `enabled`.
+pub struct KeyTones<'a, K> {
+    keypad: &'a mut K,
+    clicks: &'a Clicks,
+    enabled: bool,
+}
+
+impl<'a, K> KeyTones<'a, K> {
+    pub fn new(keypad: &'a mut K, clicks: &'a Clicks, enabled: bool) -> Self {
+        Self {
+            keypad,
+            clicks,
+            enabled,
+        }
+    }
+}
+
+impl<K: Keypad> Keypad for KeyTones<'_, K> {
+    async fn event(&mut self) -> KeyEvent {
+        self.timed_event().await.event
+    }
+
+    async fn timed_event(&mut self) -> TimedKeyEvent {
+        let event = self.keypad.timed_event().await;
+        if self.enabled && matches!(event.event, KeyEvent::Down(_)) {
+            self.clicks.request();
+        }
+        event
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    const THRESHOLDS: Thresholds = Thresholds {
+        long_press: Duration::from_millis(40),
+        repeat: Duration::from_millis(10),
+    };
+
+    #[test]
+    fn test_tap() {
+        let mut keypad = fake::Keypad::default();
+        keypad.push(KeyEvent::Down(Key::Five));
+        keypad.push(KeyEvent::Up(Key::Five));
+        let mut keypad = Gestures::with_thresholds(keypad, THRESHOLDS);
+
+        embassy_futures::block_on(async {
+            assert_eq!(keypad.event().await, KeyEvent::Down(Key::Five));
+            assert_eq!(keypad.event().await, KeyEvent::Up(Key::Five));
+        });
+        assert!(keypad.held.is_none());
+    }
+
+    #[test]
+    fn test_hold() {
+        let mut keypad = fake::Keypad::default();
+        keypad.push(KeyEvent::Down(Key::Cancel));
+        let mut keypad = Gestures::with_thresholds(keypad, THRESHOLDS);
+
+        embassy_futures::block_on(async {
+            let down = keypad.timed_event().await;
+            assert_eq!(down.event, KeyEvent::Down(Key::Cancel));
+
+            let long_press = keypad.timed_event().await;
+            assert_eq!(long_press.event, KeyEvent::LongPress(Key::Cancel));
+            assert!(long_press.at >= down.at + THRESHOLDS.long_press);
+
+            let repeat = keypad.timed_event().await;
+            assert_eq!(repeat.event, KeyEvent::Repeat(Key::Cancel));
+            assert!(repeat.at >= long_press.at + THRESHOLDS.repeat);
+            assert_eq!(keypad.event().await, KeyEvent::Repeat(Key::Cancel));
+
+            keypad.keypad.push(KeyEvent::Up(Key::Cancel));
+            assert_eq!(keypad.event().await, KeyEvent::Up(Key::Cancel));
+        });
+        assert!(keypad.held.is_none());
+    }
+
+    #[test]
+    fn test_repeat_after_slow_caller() {
+        let mut keypad = fake::Keypad::default();
+        keypad.push(KeyEvent::Down(Key::Down));
+        let mut keypad = Gestures::with_thresholds(keypad, THRESHOLDS);
+
+        embassy_futures::block_on(async {
+            keypad.event().await;
+            assert_eq!(keypad.event().await, KeyEvent::LongPress(Key::Down));
+            Timer::after(THRESHOLDS.repeat * 5).await;
+
+            assert_eq!(keypad.event().await, KeyEvent::Repeat(Key::Down));
+            let first = Instant::now();
+            assert_eq!(keypad.event().await, KeyEvent::Repeat(Key::Down));
+            assert!(
+                first.elapsed() >= THRESHOLDS.repeat / 2,
+                "repeats missed while busy should not come in a burst"
+            );
+        });
+    }
+
+    #[test]
+    fn test_key_tones() {
+        let mut keypad = fake::Keypad::default();
+        keypad.push(KeyEvent::Down(Key::Five));
+        let mut buzzer = fake::Buzzer::default();
+        let clicks = Clicks::new();
+
+        embassy_futures::block_on(select(
+            async {
+                KeyTones::new(&mut keypad, &clicks, true).event().await;
+                // long enough for the click to start but not finish
+                Timer::after(TONE_LENGTH / 2).await;
+            },
+            clicks.play(&mut buzzer),
+        ));
+
+        assert_eq!(buzzer.frequency, TONE_FREQUENCY);
+        assert!(buzzer.muted, "dropping the player should not leave it on");
+    }
+}
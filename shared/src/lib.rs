@@ -1,20 +1,66 @@
 #![no_std]
 
+pub mod audio;
+pub mod backlight;
+pub mod budget;
+pub mod calendar;
+#[cfg(any(test, feature = "fake"))]
+pub mod fake;
+pub mod keyguard;
+pub mod keypad;
+pub mod lifecycle;
 pub mod menu;
+pub mod protocol;
+pub mod registry;
+pub mod settings;
+pub mod soft_keys;
+pub mod status;
+pub mod storage;
+pub mod system;
+pub mod text_input;
+pub mod time_zone;
+pub mod vibration;
+pub mod widget;
 
-use core::{fmt::Debug, future::Future};
+use core::{cell::RefCell, fmt::Debug, future::Future};
 
-use embedded_graphics::{Drawable, prelude::Primitive, primitives::PrimitiveStyle};
+use backlight::{Held, Timeout, Waking};
+use budget::{Budget, Outcome, Timing};
+use embassy_futures::select::{Either, Either4, select, select4};
+use embassy_time::{Duration, Instant, Timer};
+use embedded_graphics::{
+    Drawable, Pixel,
+    draw_target::DrawTargetExt,
+    prelude::{Dimensions, Point, Primitive},
+    primitives::{PrimitiveStyle, Rectangle},
+};
 use embedded_graphics_core::{draw_target::DrawTarget, pixelcolor::BinaryColor};
+use keyguard::{Guarded, Keyguard};
+use lifecycle::Lifecycle;
+use soft_keys::SoftKeys;
+use status::Status;
+pub use storage::Storage;
+use system::System;
+use time_zone::Local;
 
+/// Apps get one that holds the backlight on, see [`backlight`].
 pub trait Backlight {
     fn on(&mut self);
     fn off(&mut self);
+    /// Percent, used whenever it is on.
+    fn set_brightness(&mut self, brightness: u8);
+}
+
+pub trait Display: DrawTarget<Color = BinaryColor, Error: Debug> {
+    /// Percent, from faintest to darkest.
+    fn set_contrast(&mut self, contrast: u8);
 }
 
 pub trait VibrationMotor {
     fn start(&mut self);
     fn stop(&mut self);
+    /// Percent, used whenever it runs.
+    fn set_intensity(&mut self, intensity: u8);
 }
 
 pub trait Buzzer {
@@ -31,12 +77,35 @@ pub enum ButtonEvent {
 
 pub trait PowerButton {
     fn was_pressed(&mut self) -> impl core::future::Future<Output = bool> + core::marker::Send;
+
+    /// Resolves once the button is pressed.  Polls `was_pressed` unless the
+    /// implementation can wait on the button directly.
+    fn wait_for_press(&mut self) -> impl core::future::Future<Output = ()> {
+        async {
+            while !self.was_pressed().await {
+                embassy_time::Timer::after_millis(10).await;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RtcError {
+    /// The clock cannot hold this time.
+    OutOfRange,
+    /// The clock did not take the time, eg. it stopped answering on I2C.
+    Bus,
 }
 
 pub trait Rtc {
+    /// Unix time, in seconds, or local time for apps, which are given a
+    /// [`time_zone::Local`].
     fn timestamp(&mut self) -> i64;
+
+    fn set_timestamp(&mut self, timestamp: i64) -> Result<(), RtcError>;
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Key {
     Select,
     Cancel,
@@ -56,109 +125,1121 @@ pub enum Key {
     Hash,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum KeyEvent {
     Up(Key),
     Down(Key),
+    /// The key has been held down past [`keypad::Thresholds::long_press`].
+    LongPress(Key),
+    /// The key is still held down, sent every
+    /// [`keypad::Thresholds::repeat`] after the long press.
+    Repeat(Key),
 }
 
-pub trait Keypad {
+/// A [`KeyEvent`] and when it happened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimedKeyEvent {
+    pub event: KeyEvent,
+    pub at: Instant,
+}
+
+/// `Send` so that the futures of wrappers borrowing a keypad are too.
+pub trait Keypad: Send {
     fn event(&mut self) -> impl core::future::Future<Output = KeyEvent> + core::marker::Send;
+
+    /// Like `event`, but stamped with when the event happened.  Defaults to
+    /// when it was received, for keypads that cannot tell any better.
+    fn timed_event(
+        &mut self,
+    ) -> impl core::future::Future<Output = TimedKeyEvent> + core::marker::Send {
+        let event = self.event();
+        async move {
+            let event = event.await;
+            TimedKeyEvent {
+                event,
+                at: Instant::now(),
+            }
+        }
+    }
+}
+
+impl<K: Keypad> Keypad for &mut K {
+    fn event(&mut self) -> impl core::future::Future<Output = KeyEvent> + core::marker::Send {
+        (**self).event()
+    }
+
+    fn timed_event(
+        &mut self,
+    ) -> impl core::future::Future<Output = TimedKeyEvent> + core::marker::Send {
+        (**self).timed_event()
+    }
+}
+
+/// Everything an application can touch on the device.
+///
+/// Implemented once per target (firmware, web simulator, `fake::Board`) so
+/// that adding a peripheral means adding an associated type here rather than
+/// another argument to every [`Application::run`].
+pub trait Board {
+    type VibrationMotor: VibrationMotor;
+    type Buzzer: Buzzer;
+    type Display: Display;
+    type Keypad: Keypad;
+    type Rtc: Rtc;
+    type Backlight: Backlight;
+    type Storage: Storage;
+    type PowerButton: PowerButton;
+
+    /// Borrows every peripheral at once, for code that needs more than one
+    /// at the same time (eg. drawing a menu while waiting on the keypad).
+    fn peripherals(&mut self) -> Peripherals<'_, Self>;
+
+    fn vibration_motor(&mut self) -> &mut Self::VibrationMotor {
+        self.peripherals().vibration_motor
+    }
+
+    fn buzzer(&mut self) -> &mut Self::Buzzer {
+        self.peripherals().buzzer
+    }
+
+    fn display(&mut self) -> &mut Self::Display {
+        self.peripherals().display
+    }
+
+    fn keypad(&mut self) -> &mut Self::Keypad {
+        self.peripherals().keypad
+    }
+
+    fn rtc(&mut self) -> &mut Self::Rtc {
+        self.peripherals().rtc
+    }
+
+    fn backlight(&mut self) -> &mut Self::Backlight {
+        self.peripherals().backlight
+    }
+
+    fn storage(&mut self) -> &mut Self::Storage {
+        self.peripherals().storage
+    }
+
+    fn power_button(&mut self) -> &mut Self::PowerButton {
+        self.peripherals().power_button
+    }
+}
+
+pub struct Peripherals<'a, B: Board + ?Sized> {
+    pub vibration_motor: &'a mut B::VibrationMotor,
+    pub buzzer: &'a mut B::Buzzer,
+    pub display: &'a mut B::Display,
+    pub keypad: &'a mut B::Keypad,
+    pub rtc: &'a mut B::Rtc,
+    pub backlight: &'a mut B::Backlight,
+    pub storage: &'a mut B::Storage,
+    pub power_button: &'a mut B::PowerButton,
 }
 
 pub trait Application {
-    // should record:
-    // how long this takes
-    // how long between calls
-    #[allow(clippy::too_many_arguments)]
-    fn run<D: DrawTarget<Color = BinaryColor>>(
+    fn run(
         &mut self,
-        vibration_motor: &mut impl VibrationMotor,
-        buzzer: &mut impl Buzzer,
-        display: &mut D,
-        keypad: &mut impl Keypad,
-        rtc: &mut impl Rtc,
-        backlight: &mut impl Backlight,
-        system_response: Option<[u8; 64]>,
-    ) -> impl Future<Output = Option<UsbTx>>
-    where
-        <D as DrawTarget>::Error: Debug;
+        board: &mut impl Board,
+        system_response: Option<SystemResponse>,
+    ) -> impl Future<Output = Option<SystemRequest>>;
+
+    /// The time budget for each call to `run`.
+    fn budget(&self) -> Budget {
+        Budget::DEFAULT
+    }
+
+    /// Whether the [`status`] strip is shown above the app.  Apps that do
+    /// draw the whole screen get all 84x48 pixels.
+    fn status_bar(&self) -> bool {
+        true
+    }
+
+    /// Labels for Select and Cancel, drawn by the system in a bar below the
+    /// app.  Apps that label their keys themselves, eg. in a [`Menu`](menu::Menu),
+    /// declare none and keep the whole height.  Asked before each `run`, so
+    /// an app can declare none while it shows a dialog with its own.
+    fn soft_keys(&self) -> SoftKeys<'static, ()> {
+        SoftKeys::new()
+    }
+
+    /// Called once, before the first `run`.
+    fn on_start(&mut self, _board: &mut impl Board) -> impl Future<Output = ()> {
+        async {}
+    }
+
+    /// Called when the system interrupts the app, eg. when a `run` overruns
+    /// its time budget.  Followed by `on_resume` or `on_exit`.
+    fn on_suspend(&mut self, _board: &mut impl Board) -> impl Future<Output = ()> {
+        async {}
+    }
+
+    /// Called before the first `run` after `on_suspend`.
+    fn on_resume(&mut self, _board: &mut impl Board) -> impl Future<Output = ()> {
+        async {}
+    }
+
+    /// Called once, before the app is dropped.  Not called if the app never
+    /// started.
+    fn on_exit(&mut self, _board: &mut impl Board) -> impl Future<Output = ()> {
+        async {}
+    }
+}
+
+/// Maximum length of text passed between apps and the system.
+pub const TEXT_CAPACITY: usize = 32;
+
+/// Something an app asks the system to do, by returning it from
+/// [`Application::run`].  The outcome is passed to the next `run` as a
+/// [`SystemResponse`].
+#[derive(Clone, Debug)]
+pub enum SystemRequest {
+    /// Set the RTC to a time read as the app's RTC reads, ie. local time.
+    SetTime(i64),
+    /// Store and apply one of the [`settings`].
+    SaveSetting {
+        key: &'static str,
+        value: i32,
+    },
+    /// Exit and launch the app registered under this name.
+    Launch(&'static str),
+    /// Ask the user for text.  Answered with [`SystemResponse::Text`] or
+    /// [`SystemResponse::Cancelled`].
+    TextInput {
+        prompt: &'static str,
+    },
+    /// Play an RTTTL ringtone.
+    PlayRingtone(&'static str),
+    /// Show a message until a key is pressed or it times out.
+    Notify(heapless::String<TEXT_CAPACITY>),
+    Usb(UsbTx),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequestError {
+    /// This target cannot fulfil the request.
+    Unsupported,
+    /// The request could not be fulfilled right now, eg. USB is not connected.
+    Unavailable,
 }
 
-pub enum SystemRequest {}
+#[derive(Clone, Debug)]
+pub enum SystemResponse {
+    /// The previous `run` went over the hard [`Budget`] and was cancelled
+    /// after `elapsed`.
+    Preempted {
+        elapsed: Duration,
+    },
+    Usb(UsbRx),
+    /// The request was fulfilled.
+    Done,
+    Text(heapless::String<TEXT_CAPACITY>),
+    /// The user backed out of a modal request.
+    Cancelled,
+    Failed(RequestError),
+}
 
-pub type UsbRx = [u8; 64];
+pub type UsbRx = protocol::Message;
+#[derive(Clone, Debug)]
 pub enum UsbTx {
-    CdcBuffer([u8; 64]),
+    Cdc(protocol::Message),
     HidChar(char),
 }
 
-// decide your time budgets
-// 'trust' application takes at most 750ms
-// force pre-emption at 1500ms
-// how do you progress things inside app that take longer than 750?
-// special kind of timer?
-// forced pre-emption should be signalled back to application + print log entry
-#[allow(clippy::too_many_arguments)]
-pub async fn run_app<D: DrawTarget<Color = BinaryColor>>(
-    mut app: impl Application,
-    vibration_motor: &mut impl VibrationMotor,
-    buzzer: &mut impl Buzzer,
-    display: &mut D,
-    keypad: &mut impl Keypad,
-    rtc: &mut impl Rtc,
-    light: &mut impl Backlight,
-    power: &mut impl PowerButton,
-    // just usb rx for now
-    system_response: Option<[u8; 64]>,
-    // just usb tx for now
-) -> Option<UsbTx>
-where
-    <D as DrawTarget>::Error: Debug,
-{
+fn reset(board: &mut impl Board) {
+    let display = board.display();
     let fill = PrimitiveStyle::with_fill(BinaryColor::On);
     display
         .bounding_box()
         .into_styled(fill)
         .draw(display)
         .unwrap();
-    buzzer.mute();
-    vibration_motor.stop();
+    board.buzzer().mute();
+    board.vibration_motor().stop();
+    board.vibration_motor().set_intensity(100);
+}
+
+/// Draws the status strip, returning what it shows.
+fn draw_status(board: &mut impl Board, system: &mut impl System) -> Status {
+    let settings = *system.settings();
+    let status = Status {
+        timestamp: settings.time_zone().to_local(board.rtc().timestamp()),
+        clock_24h: settings.clock_24h,
+        usb_connected: system.usb_connected(),
+        locked: system.keyguard().is_locked(),
+        battery: system.battery(),
+    };
+    status::draw(&status, board.display());
+    status
+}
 
+/// Redraws the status strip at the turn of every minute while `run` goes on,
+/// as it may wait for keys for far longer.  Does nothing without a strip.
+async fn refresh_status<D: Display>(
+    status: Option<Status>,
+    system: &impl System,
+    screen: &Screen<'_, D>,
+) -> ! {
+    let Some(mut status) = status else {
+        loop {
+            core::future::pending::<()>().await;
+        }
+    };
+    let (drawn, at) = (status.timestamp, Instant::now());
     loop {
-        match embassy_time::with_timeout(
-            embassy_time::Duration::from_millis(1000),
-            app.run(
-                vibration_motor,
-                buzzer,
-                display,
-                keypad,
-                rtc,
-                light,
-                system_response,
-            ),
-        )
-        .await
-        {
+        let elapsed = at.elapsed().as_secs() as i64;
+        Timer::after_secs((60 - (drawn + elapsed).rem_euclid(60)) as u64).await;
+        status.timestamp = drawn + at.elapsed().as_secs() as i64;
+        status.usb_connected = system.usb_connected();
+        status.locked = system.keyguard().is_locked();
+        if let Some(display) = screen.0.borrow_mut().as_deref_mut() {
+            status::draw(&status, display);
+        }
+    }
+}
+
+/// The display while an app runs, shared between the app's [`Viewport`] and
+/// the system redrawing the [`status`] strip.
+struct Screen<'a, D>(RefCell<Option<&'a mut D>>);
+
+impl<D> Screen<'_, D> {
+    fn new() -> Self {
+        Self(RefCell::new(None))
+    }
+}
+
+/// The part of a display an app draws in, with its origin moved to the top
+/// left of that part.
+pub struct Viewport<'a, D> {
+    screen: &'a Screen<'a, D>,
+    area: Rectangle,
+}
+
+impl<D: Display> DrawTarget for Viewport<'_, D> {
+    type Color = BinaryColor;
+    type Error = D::Error;
+
+    fn draw_iter<I: IntoIterator<Item = Pixel<Self::Color>>>(
+        &mut self,
+        pixels: I,
+    ) -> Result<(), Self::Error> {
+        match self.screen.0.borrow_mut().as_deref_mut() {
+            Some(display) => display.cropped(&self.area).draw_iter(pixels),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<D> Dimensions for Viewport<'_, D> {
+    fn bounding_box(&self) -> Rectangle {
+        Rectangle::new(Point::zero(), self.area.size)
+    }
+}
+
+impl<D: Display> Display for Viewport<'_, D> {
+    fn set_contrast(&mut self, contrast: u8) {
+        if let Some(display) = self.screen.0.borrow_mut().as_deref_mut() {
+            display.set_contrast(contrast);
+        }
+    }
+}
+
+/// A vibration motor that only starts if vibration is turned on in the
+/// [`settings`].
+pub struct Gated<'a, M> {
+    motor: &'a mut M,
+    enabled: bool,
+}
+
+impl<M: VibrationMotor> VibrationMotor for Gated<'_, M> {
+    fn start(&mut self) {
+        if self.enabled {
+            self.motor.start();
+        }
+    }
+
+    fn stop(&mut self) {
+        self.motor.stop();
+    }
+
+    fn set_intensity(&mut self, intensity: u8) {
+        self.motor.set_intensity(intensity);
+    }
+}
+
+/// A buzzer that stays quiet in silent mode, and whose volume is a share of
+/// the one in the [`settings`].
+pub struct Volume<'a, B> {
+    buzzer: &'a mut B,
+    volume: u8,
+    silent: bool,
+}
+
+impl<'a, B> Volume<'a, B> {
+    pub fn new(buzzer: &'a mut B, settings: &settings::Settings) -> Self {
+        Self {
+            buzzer,
+            volume: settings.volume,
+            silent: settings.silent,
+        }
+    }
+}
+
+impl<B: Buzzer> Buzzer for Volume<'_, B> {
+    fn set_frequency(&mut self, frequency: u16) {
+        self.buzzer.set_frequency(frequency);
+    }
+
+    fn set_volume(&mut self, volume: u8) {
+        let share = u16::from(volume.min(100)) * u16::from(self.volume) / 100;
+        self.buzzer.set_volume(share as u8);
+    }
+
+    fn mute(&mut self) {
+        self.buzzer.mute();
+    }
+
+    fn unmute(&mut self) {
+        if !self.silent {
+            self.buzzer.unmute();
+        }
+    }
+}
+
+/// The board as seen by a running app: everything but the power button, which
+/// the system holds on to so that a press can cancel `run` straight away, and
+/// with the keypad behind the [`Keyguard`], the display below any [`status`]
+/// strip, the backlight left to the system's [`Timeout`] and the motor,
+/// buzzer and RTC subject to the [`settings`].
+struct AppBoard<'a, B: Board + ?Sized> {
+    vibration_motor: Gated<'a, B::VibrationMotor>,
+    buzzer: Volume<'a, B::Buzzer>,
+    display: Viewport<'a, B::Display>,
+    // no key tones, which only the system's menus and dialogs play: the app
+    // has the buzzer, and clicks would cut across its own sounds
+    keypad: Waking<'a, Guarded<'a, B::Keypad>>,
+    rtc: Local<'a, B::Rtc>,
+    backlight: Held<'a>,
+    storage: &'a mut B::Storage,
+    power_button: NeverPressed,
+}
+
+struct NeverPressed;
+
+impl PowerButton for NeverPressed {
+    async fn was_pressed(&mut self) -> bool {
+        false
+    }
+
+    async fn wait_for_press(&mut self) {
+        core::future::pending().await
+    }
+}
+
+impl<'a, B: Board + ?Sized> Board for AppBoard<'a, B> {
+    type VibrationMotor = Gated<'a, B::VibrationMotor>;
+    type Buzzer = Volume<'a, B::Buzzer>;
+    type Display = Viewport<'a, B::Display>;
+    type Keypad = Waking<'a, Guarded<'a, B::Keypad>>;
+    type Rtc = Local<'a, B::Rtc>;
+    type Backlight = Held<'a>;
+    type Storage = B::Storage;
+    type PowerButton = NeverPressed;
+
+    fn peripherals(&mut self) -> Peripherals<'_, Self> {
+        Peripherals {
+            vibration_motor: &mut self.vibration_motor,
+            buzzer: &mut self.buzzer,
+            display: &mut self.display,
+            keypad: &mut self.keypad,
+            rtc: &mut self.rtc,
+            backlight: &mut self.backlight,
+            storage: self.storage,
+            power_button: &mut self.power_button,
+        }
+    }
+}
+
+/// Splits `board` into what the app gets and the power button and backlight
+/// the system keeps.  The display goes on `screen`, for the app to draw on
+/// through a [`Viewport`].
+fn without_power_button<'a, B: Board>(
+    board: &'a mut B,
+    screen: &'a Screen<'a, B::Display>,
+    keyguard: &'a Keyguard,
+    timeout: &'a Timeout,
+    status_bar: bool,
+    soft_keys: bool,
+    settings: &settings::Settings,
+) -> (
+    AppBoard<'a, B>,
+    &'a mut B::PowerButton,
+    &'a mut B::Backlight,
+) {
+    let Peripherals {
+        vibration_motor,
+        buzzer,
+        display,
+        keypad,
+        rtc,
+        backlight,
+        storage,
+        power_button,
+    } = board.peripherals();
+
+    let mut area = display.bounding_box();
+    if status_bar {
+        area = status::below(area);
+    }
+    if soft_keys {
+        area = soft_keys::above(area);
+    }
+
+    (
+        AppBoard {
+            vibration_motor: Gated {
+                motor: vibration_motor,
+                enabled: settings.vibration,
+            },
+            buzzer: Volume::new(buzzer, settings),
+            display: {
+                *screen.0.borrow_mut() = Some(display);
+                Viewport { screen, area }
+            },
+            keypad: Waking::new(Guarded::new(keypad, keyguard), timeout),
+            rtc: Local::new(rtc, settings.time_zone()),
+            backlight: Held(timeout),
+            storage,
+            power_button: NeverPressed,
+        },
+        power_button,
+        backlight,
+    )
+}
+
+/// The board as `run` gets it, for the app's lifecycle callbacks.
+fn app_board<'a, B: Board>(
+    board: &'a mut B,
+    screen: &'a Screen<'a, B::Display>,
+    system: &'a mut impl System,
+    status_bar: bool,
+    soft_keys: bool,
+) -> AppBoard<'a, B> {
+    let settings = *system.settings();
+    let system = &*system;
+    let (app_board, ..) = without_power_button(
+        board,
+        screen,
+        system.keyguard(),
+        system.backlight(),
+        status_bar,
+        soft_keys,
+        &settings,
+    );
+    app_board
+}
+
+/// Runs `app` until it asks to launch another app, returning that app's name,
+/// or until the power button is pressed.  Other requests are fulfilled by
+/// `system`, the app's [`Budget`] is enforced and its keys go through the
+/// [`Keyguard`].
+pub async fn run_app(
+    app: impl Application,
+    board: &mut impl Board,
+    system: &mut impl System,
+) -> Option<&'static str> {
+    let mut timing = Timing::default();
+    let result = run_app_with_timing(app, board, system, &mut timing).await;
+    log::info!(
+        "app exited after {} runs: longest run {}ms, longest gap {}ms, {} over soft budget, {} pre-empted",
+        timing.runs,
+        timing.longest_run.as_millis(),
+        timing.longest_gap.as_millis(),
+        timing.soft_overruns,
+        timing.preemptions
+    );
+    result
+}
+
+/// [`run_app`], recording how long each `run` takes into `timing`.
+pub async fn run_app_with_timing(
+    app: impl Application,
+    board: &mut impl Board,
+    system: &mut impl System,
+    timing: &mut Timing,
+) -> Option<&'static str> {
+    reset(board);
+
+    let budget = app.budget();
+    let status_bar = app.status_bar();
+    let mut labelled = !app.soft_keys().is_empty();
+    system.backlight().release();
+    // undoes any volume the previous app set
+    system.settings().apply(board);
+    let mut app = Lifecycle::new(app);
+    app.start(&mut app_board(
+        board,
+        &Screen::new(),
+        system,
+        status_bar,
+        labelled,
+    ))
+    .await;
+    let mut pending = None;
+
+    loop {
+        let response = match pending.take() {
+            Some(response) => Some(response),
+            None => system.receive(board).await.map(SystemResponse::Usb),
+        };
+        let status = status_bar.then(|| draw_status(board, system));
+        let soft_keys = app.app().soft_keys();
+        labelled = !soft_keys.is_empty();
+        if labelled {
+            soft_keys.draw(board.display());
+        }
+        let settings = *system.settings();
+        let started = Instant::now();
+        let result = {
+            let system = &*system;
+            let keyguard = system.keyguard();
+            let timeout = system.backlight();
+            let screen = Screen::new();
+            let (mut app_board, power_button, backlight) = without_power_button(
+                board, &screen, keyguard, timeout, status_bar, labelled, &settings,
+            );
+            embassy_time::with_timeout(
+                budget.hard,
+                select4(
+                    app.run(&mut app_board, response),
+                    power_button.wait_for_press(),
+                    keyguard.notice(),
+                    select(
+                        timeout.drive(backlight),
+                        refresh_status(status, system, &screen),
+                    ),
+                ),
+            )
+            .await
+        };
+        let result = match result {
+            Ok(Either4::First(result)) => Ok(result),
+            Ok(Either4::Second(())) => {
+                log::info!("power button pressed during run");
+                app.exit(&mut app_board(
+                    board,
+                    &Screen::new(),
+                    system,
+                    status_bar,
+                    labelled,
+                ))
+                .await;
+                reset(board);
+
+                return None;
+            }
+            Ok(Either4::Third(notice)) => {
+                app.suspend(&mut app_board(
+                    board,
+                    &Screen::new(),
+                    system,
+                    status_bar,
+                    labelled,
+                ))
+                .await;
+                keyguard::show(notice, board, system.keyguard()).await;
+                backlight::after_notice(system);
+                continue;
+            }
+            Ok(Either4::Fourth(Either::First(never) | Either::Second(never))) => match never {},
+            Err(e) => Err(e),
+        };
+        let finished = Instant::now();
+
+        match timing.record(&budget, started, finished, &result) {
+            Outcome::Completed => {}
+            Outcome::OverSoftBudget => {
+                log::warn!(
+                    "app took {}ms, over soft budget of {}ms",
+                    timing.last_run.as_millis(),
+                    budget.soft.as_millis()
+                );
+            }
+            Outcome::Preempted => {
+                log::warn!(
+                    "app pre-empted after {}ms, hard budget is {}ms",
+                    timing.last_run.as_millis(),
+                    budget.hard.as_millis()
+                );
+            }
+        }
+
+        match result {
             Ok(None) => {}
-            Ok(e) => {
-                return e;
+            Ok(Some(SystemRequest::Launch(name))) => {
+                app.exit(&mut app_board(
+                    board,
+                    &Screen::new(),
+                    system,
+                    status_bar,
+                    labelled,
+                ))
+                .await;
+                reset(board);
+
+                return Some(name);
+            }
+            Ok(Some(request)) => {
+                if request.is_modal() {
+                    app.suspend(&mut app_board(
+                        board,
+                        &Screen::new(),
+                        system,
+                        status_bar,
+                        labelled,
+                    ))
+                    .await;
+                }
+                pending = Some(system.fulfil(request, board).await);
             }
             Err(embassy_time::TimeoutError) => {
-                log::info!("timed out");
+                pending = Some(SystemResponse::Preempted {
+                    elapsed: timing.last_run,
+                });
+                app.suspend(&mut app_board(
+                    board,
+                    &Screen::new(),
+                    system,
+                    status_bar,
+                    labelled,
+                ))
+                .await;
             }
         }
 
-        if power.was_pressed().await {
-            let fill = PrimitiveStyle::with_fill(BinaryColor::On);
-            display
-                .bounding_box()
-                .into_styled(fill)
-                .draw(display)
-                .unwrap();
-            buzzer.mute();
-            vibration_motor.stop();
+        if board.power_button().was_pressed().await {
+            app.exit(&mut app_board(
+                board,
+                &Screen::new(),
+                system,
+                status_bar,
+                labelled,
+            ))
+            .await;
+            reset(board);
 
             return None;
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use embedded_graphics::prelude::*;
+
+    use super::*;
+
+    struct Counter(usize);
+
+    impl Application for Counter {
+        async fn run(
+            &mut self,
+            board: &mut impl Board,
+            _system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            self.0 += 1;
+            board.vibration_motor().start();
+            None
+        }
+    }
+
+    #[test]
+    fn test_run_app_until_power_pressed() {
+        let mut board = fake::Board::default();
+        board.power_button.press_after(3);
+
+        let result = embassy_futures::block_on(run_app(
+            Counter(0),
+            &mut board,
+            &mut fake::System::default(),
+        ));
+
+        assert!(result.is_none());
+        assert!(!board.vibration_motor.running);
+        assert_eq!(
+            board.display.pixel(Point::zero()),
+            Some(BinaryColor::On),
+            "display should be cleared on exit"
+        );
+    }
+
+    struct Slow<'a> {
+        preempted: &'a mut Option<Duration>,
+    }
+
+    impl Application for Slow<'_> {
+        async fn run(
+            &mut self,
+            _board: &mut impl Board,
+            system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            if let Some(SystemResponse::Preempted { elapsed }) = system_response {
+                *self.preempted = Some(elapsed);
+                return Some(SystemRequest::Launch("next"));
+            }
+            embassy_time::Timer::after_millis(50).await;
+            None
+        }
+
+        fn budget(&self) -> Budget {
+            Budget {
+                soft: Duration::from_millis(5),
+                hard: Duration::from_millis(10),
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_app_preempts() {
+        let mut board = fake::Board::default();
+        let mut preempted = None;
+        let mut timing = Timing::default();
+
+        let result = embassy_futures::block_on(run_app_with_timing(
+            Slow {
+                preempted: &mut preempted,
+            },
+            &mut board,
+            &mut fake::System::default(),
+            &mut timing,
+        ));
+
+        assert_eq!(result, Some("next"));
+        assert!(preempted.unwrap() >= Duration::from_millis(10));
+        assert_eq!(timing.runs, 2);
+        assert_eq!(timing.preemptions, 1);
+        assert!(timing.longest_run >= Duration::from_millis(10));
+    }
+
+    struct Blocked;
+
+    impl Application for Blocked {
+        async fn run(
+            &mut self,
+            board: &mut impl Board,
+            _system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            board.vibration_motor().start();
+            board.keypad().event().await;
+            Some(SystemRequest::Launch("next"))
+        }
+    }
+
+    #[test]
+    fn test_power_button_cancels_run() {
+        let mut board = fake::Board::default();
+        board.power_button.press_after(2);
+
+        let result =
+            embassy_futures::block_on(run_app(Blocked, &mut board, &mut fake::System::default()));
+
+        assert!(result.is_none());
+        assert!(!board.vibration_motor.running);
+        assert!(board.buzzer.muted);
+    }
+
+    struct Echo {
+        responses: heapless::Vec<SystemResponse, 4>,
+    }
+
+    impl Application for Echo {
+        async fn run(
+            &mut self,
+            _board: &mut impl Board,
+            system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            match system_response {
+                Some(SystemResponse::Usb(rx)) => Some(SystemRequest::Usb(UsbTx::Cdc(rx))),
+                Some(response) => {
+                    self.responses.push(response).unwrap();
+                    Some(SystemRequest::Launch("next"))
+                }
+                None => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_requests_fulfilled() {
+        let mut board = fake::Board::default();
+        let mut system = fake::System::default();
+        system.received.push_back(protocol::Message::Ping).unwrap();
+        system
+            .received
+            .push_back(protocol::Message::Data(
+                heapless::Vec::from_slice(&[1]).unwrap(),
+            ))
+            .unwrap();
+
+        let result = embassy_futures::block_on(run_app(
+            Echo {
+                responses: heapless::Vec::new(),
+            },
+            &mut board,
+            &mut system,
+        ));
+
+        assert_eq!(result, Some("next"));
+        assert!(matches!(
+            &system.sent[..],
+            [
+                UsbTx::Cdc(protocol::Message::Pong),
+                UsbTx::Cdc(protocol::Message::Data(data)),
+            ] if data[..] == [1]
+        ));
+    }
+
+    struct Typist<'a> {
+        keys: &'a mut heapless::Vec<KeyEvent, 8>,
+    }
+
+    impl Application for Typist<'_> {
+        async fn run(
+            &mut self,
+            board: &mut impl Board,
+            _system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            let event = board.keypad().event().await;
+            self.keys.push(event).unwrap();
+            None
+        }
+    }
+
+    #[test]
+    fn test_keyguard() {
+        let mut board = fake::Board::default();
+        let mut system = fake::System::default();
+        board.power_button.press_after(50);
+        // Two dismisses the "Keys unlocked" notice
+        for key in [
+            Key::Select,
+            Key::Asterisk,
+            Key::Five,
+            Key::Select,
+            Key::Asterisk,
+            Key::Two,
+            Key::Three,
+        ] {
+            board.keypad.push(KeyEvent::Down(key));
+        }
+        let mut keys = heapless::Vec::new();
+
+        embassy_futures::block_on(run_app(Typist { keys: &mut keys }, &mut board, &mut system));
+
+        assert_eq!(keys, [KeyEvent::Down(Key::Three)]);
+        assert!(!system.keyguard.is_locked());
+    }
+
+    struct Painter<'a> {
+        area: &'a mut Option<embedded_graphics::primitives::Rectangle>,
+        status_bar: bool,
+        soft_keys: SoftKeys<'static, ()>,
+    }
+
+    impl Application for Painter<'_> {
+        async fn run(
+            &mut self,
+            board: &mut impl Board,
+            _system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            *self.area = Some(board.display().bounding_box());
+            None
+        }
+
+        fn status_bar(&self) -> bool {
+            self.status_bar
+        }
+
+        fn soft_keys(&self) -> SoftKeys<'static, ()> {
+            self.soft_keys
+        }
+    }
+
+    #[test]
+    fn test_status_bar() {
+        for status_bar in [true, false] {
+            let mut board = fake::Board::default();
+            board.power_button.press_after(1);
+            let mut area = None;
+
+            embassy_futures::block_on(run_app(
+                Painter {
+                    area: &mut area,
+                    status_bar,
+                    soft_keys: SoftKeys::new(),
+                },
+                &mut board,
+                &mut fake::System::default(),
+            ));
+
+            let height = fake::HEIGHT as u32 - if status_bar { status::HEIGHT } else { 0 };
+            assert_eq!(area.unwrap().size, Size::new(fake::WIDTH as u32, height));
+        }
+    }
+
+    struct Tidy<'a> {
+        area: &'a mut Option<embedded_graphics::primitives::Rectangle>,
+    }
+
+    impl Application for Tidy<'_> {
+        async fn run(
+            &mut self,
+            _board: &mut impl Board,
+            _system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            None
+        }
+
+        async fn on_start(&mut self, board: &mut impl Board) {
+            *self.area = Some(board.display().bounding_box());
+        }
+
+        async fn on_exit(&mut self, board: &mut impl Board) {
+            board.backlight().on();
+        }
+    }
+
+    #[test]
+    fn test_callbacks_get_app_board() {
+        let mut board = fake::Board::default();
+        let mut system = fake::System::default();
+        board.power_button.press_after(1);
+        let mut area = None;
+
+        embassy_futures::block_on(run_app(Tidy { area: &mut area }, &mut board, &mut system));
+
+        let height = fake::HEIGHT as u32 - status::HEIGHT;
+        assert_eq!(area.unwrap().size, Size::new(fake::WIDTH as u32, height));
+        assert!(!board.backlight.on, "the backlight goes through the system");
+    }
+
+    #[test]
+    fn test_status_refreshed_each_minute() {
+        let mut system = fake::System::default();
+        let mut display = fake::Display::default();
+        let status = Status {
+            timestamp: 59,
+            clock_24h: true,
+            usb_connected: false,
+            locked: false,
+            battery: None,
+        };
+        status::draw(&status, &mut display);
+        system.usb_connected = true;
+
+        {
+            let screen = Screen::new();
+            *screen.0.borrow_mut() = Some(&mut display);
+            embassy_futures::block_on(select(
+                refresh_status(Some(status), &system, &screen),
+                Timer::after_millis(1_100),
+            ));
+        }
+
+        let mut expected = fake::Display::default();
+        let status = Status {
+            timestamp: 60,
+            usb_connected: true,
+            ..status
+        };
+        status::draw(&status, &mut expected);
+        for y in 0..status::HEIGHT as i32 {
+            for x in 0..fake::WIDTH as i32 {
+                let point = Point::new(x, y);
+                assert_eq!(display.pixel(point), expected.pixel(point), "at {point}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_soft_keys() {
+        let mut board = fake::Board::default();
+        board.power_button.press_after(1);
+        let mut area = None;
+
+        embassy_futures::block_on(run_app(
+            Painter {
+                area: &mut area,
+                status_bar: false,
+                soft_keys: SoftKeys::new().select("Set time", ()),
+            },
+            &mut board,
+            &mut fake::System::default(),
+        ));
+
+        let height = fake::HEIGHT as u32 - soft_keys::HEIGHT;
+        assert_eq!(area.unwrap().size, Size::new(fake::WIDTH as u32, height));
+    }
+
+    struct Dialog<'a> {
+        area: &'a mut Option<embedded_graphics::primitives::Rectangle>,
+        open: bool,
+    }
+
+    impl Application for Dialog<'_> {
+        async fn run(
+            &mut self,
+            board: &mut impl Board,
+            _system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            *self.area = Some(board.display().bounding_box());
+            self.open = true;
+            None
+        }
+
+        fn status_bar(&self) -> bool {
+            false
+        }
+
+        fn soft_keys(&self) -> SoftKeys<'static, ()> {
+            if self.open {
+                SoftKeys::new()
+            } else {
+                SoftKeys::new().select("Open", ())
+            }
+        }
+    }
+
+    #[test]
+    fn test_soft_keys_asked_each_run() {
+        let mut board = fake::Board::default();
+        board.power_button.press_after(1);
+        let mut area = None;
+
+        embassy_futures::block_on(run_app(
+            Dialog {
+                area: &mut area,
+                open: false,
+            },
+            &mut board,
+            &mut fake::System::default(),
+        ));
+
+        let size = Size::new(fake::WIDTH as u32, fake::HEIGHT as u32);
+        assert_eq!(area.unwrap().size, size, "the dialog gets the soft key row");
+    }
+
+    #[test]
+    fn test_peripherals_borrowed_together() {
+        let mut board = fake::Board::default();
+        board.rtc.0 = 1_000;
+
+        let peripherals = board.peripherals();
+        peripherals.backlight.on();
+        let timestamp = peripherals.rtc.timestamp();
+
+        assert_eq!(timestamp, 1_000);
+        assert!(board.backlight.on);
+    }
+}
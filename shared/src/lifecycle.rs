@@ -0,0 +1,173 @@
+//! Enforces the order in which [`Application`] lifecycle callbacks are made.
+//!
+//! `on_start` is always first and `on_exit` always last, each called exactly
+//! once.  `on_suspend` and `on_resume` come in pairs in between; `on_exit`
+//! may follow `on_suspend` directly if the app is closed while suspended.
+
+use crate::{Application, Board, SystemRequest, SystemResponse};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    Created,
+    Running,
+    Suspended,
+    Exited,
+}
+
+pub struct Lifecycle<A: Application> {
+    app: A,
+    state: State,
+}
+
+impl<A: Application> Lifecycle<A> {
+    pub fn new(app: A) -> Self {
+        Self {
+            app,
+            state: State::Created,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn app(&self) -> &A {
+        &self.app
+    }
+
+    pub async fn start(&mut self, board: &mut impl Board) {
+        if self.state == State::Created {
+            self.app.on_start(board).await;
+            self.state = State::Running;
+        }
+    }
+
+    pub async fn suspend(&mut self, board: &mut impl Board) {
+        if self.state == State::Running {
+            self.app.on_suspend(board).await;
+            self.state = State::Suspended;
+        }
+    }
+
+    pub async fn resume(&mut self, board: &mut impl Board) {
+        if self.state == State::Suspended {
+            self.app.on_resume(board).await;
+            self.state = State::Running;
+        }
+    }
+
+    pub async fn exit(&mut self, board: &mut impl Board) {
+        match self.state {
+            State::Running | State::Suspended => {
+                self.app.on_exit(board).await;
+                self.state = State::Exited;
+            }
+            State::Created => self.state = State::Exited,
+            State::Exited => {}
+        }
+    }
+
+    /// Runs the app once, starting or resuming it first if needed.  Does
+    /// nothing once the app has exited.
+    pub async fn run(
+        &mut self,
+        board: &mut impl Board,
+        system_response: Option<SystemResponse>,
+    ) -> Option<SystemRequest> {
+        self.start(board).await;
+        self.resume(board).await;
+        if self.state == State::Running {
+            self.app.run(board, system_response).await
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Call {
+        Start,
+        Run,
+        Suspend,
+        Resume,
+        Exit,
+    }
+
+    struct Recorder<'a>(&'a mut heapless::Vec<Call, 16>);
+
+    impl Application for Recorder<'_> {
+        async fn run(
+            &mut self,
+            _board: &mut impl Board,
+            _system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            self.0.push(Call::Run).unwrap();
+            None
+        }
+
+        async fn on_start(&mut self, _board: &mut impl Board) {
+            self.0.push(Call::Start).unwrap();
+        }
+
+        async fn on_suspend(&mut self, _board: &mut impl Board) {
+            self.0.push(Call::Suspend).unwrap();
+        }
+
+        async fn on_resume(&mut self, _board: &mut impl Board) {
+            self.0.push(Call::Resume).unwrap();
+        }
+
+        async fn on_exit(&mut self, _board: &mut impl Board) {
+            self.0.push(Call::Exit).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let mut board = fake::Board::default();
+        let mut calls = heapless::Vec::new();
+        let mut lifecycle = Lifecycle::new(Recorder(&mut calls));
+
+        embassy_futures::block_on(async {
+            lifecycle.resume(&mut board).await;
+            lifecycle.run(&mut board, None).await;
+            lifecycle.suspend(&mut board).await;
+            lifecycle.suspend(&mut board).await;
+            lifecycle.run(&mut board, None).await;
+            lifecycle.suspend(&mut board).await;
+            lifecycle.exit(&mut board).await;
+            lifecycle.exit(&mut board).await;
+            lifecycle.run(&mut board, None).await;
+        });
+        assert_eq!(lifecycle.state(), State::Exited);
+
+        assert_eq!(
+            calls,
+            [
+                Call::Start,
+                Call::Run,
+                Call::Suspend,
+                Call::Resume,
+                Call::Run,
+                Call::Suspend,
+                Call::Exit
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exit_before_start() {
+        let mut board = fake::Board::default();
+        let mut calls = heapless::Vec::new();
+        let mut lifecycle = Lifecycle::new(Recorder(&mut calls));
+
+        embassy_futures::block_on(lifecycle.exit(&mut board));
+
+        assert!(calls.is_empty());
+    }
+}
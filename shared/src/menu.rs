@@ -1,3 +1,6 @@
+use core::fmt::Write;
+
+use embassy_time::{Duration, Instant, with_timeout};
 use embedded_graphics::{
     mono_font::{MonoTextStyle, ascii::FONT_6X10},
     pixelcolor::BinaryColor,
@@ -5,68 +8,540 @@ use embedded_graphics::{
     primitives::{PrimitiveStyle, Rectangle},
     text::{Alignment, Text},
 };
+use multi_tap::Input;
+
+use super::{Key, KeyEvent, Keypad};
+use crate::{
+    soft_keys::{self, SoftKeys},
+    text_input::TAP_TIMEOUT,
+};
+
+const ROW_HEIGHT: u32 = 10;
+const CHARACTER_WIDTH: u32 = 6;
+const SCROLLBAR_WIDTH: u32 = 3;
+const TITLE_HEIGHT: u32 = 10;
+/// How often a selected label that is too wide to fit scrolls along.
+const MARQUEE_STEP: Duration = Duration::from_millis(200);
+const MARQUEE_PIXELS: u32 = 3;
+/// Steps to hold still at either end of the marquee.
+const MARQUEE_PAUSE: u32 = 5;
+/// How long after a digit the next one still adds to the item number.
+const SHORTCUT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Deepest nesting of submenus, the top level included.
+const MAX_DEPTH: usize = 4;
+const FILTER_CAPACITY: usize = 8;
+
+/// An entry in a [`Menu`], either one that can be chosen or one that opens
+/// a list of its own.
+pub enum Item<'a, T> {
+    Leaf {
+        label: &'a str,
+        id: T,
+    },
+    Submenu {
+        label: &'a str,
+        items: &'a [Item<'a, T>],
+    },
+}
 
-use super::Keypad;
+impl<'a, T> Item<'a, T> {
+    pub fn label(&self) -> &'a str {
+        match self {
+            Item::Leaf { label, .. } | Item::Submenu { label, .. } => label,
+        }
+    }
+}
 
-pub struct Menu<'a> {
-    items: &'a [&'a str],
+/// One open list along the path from the top level.
+struct Level<'a, T> {
+    items: &'a [Item<'a, T>],
+    /// Item in the parent that opened this level, if not the top level.
+    label: Option<&'a str>,
     index: usize,
+    /// First visible item in the viewport.
+    top: usize,
+}
+
+/// What Select and Cancel resolve to.
+#[derive(Clone, Copy)]
+enum Action {
+    Choose,
+    Back,
+    Erase,
 }
 
-impl<'a> Menu<'a> {
-    pub fn new(items: &'a [&'a str]) -> Self {
-        Self { items, index: 0 }
+/// Hierarchical list of items.  Up and Down move the selection, Select
+/// chooses it and Cancel goes back a level.  Digits jump straight to the
+/// numbered item, so 2 then 1 opens the second item and chooses the first
+/// in it.  In a level of ten or more items, digits typed within a second of
+/// each other make up one number, so 1 then 2 is the twelfth item.  With
+/// [`Menu::with_filter`], Hash starts typing letters that narrow the list to
+/// items starting with them.  With [`Menu::with_soft_keys`], what Select and
+/// Cancel do is labelled below the list.
+pub struct Menu<'a, T> {
+    levels: heapless::Vec<Level<'a, T>, MAX_DEPTH>,
+    marquee: u32,
+    filterable: bool,
+    labelled: bool,
+    filter: Option<Input<Key, FILTER_CAPACITY>>,
+    last_tap: Instant,
+    /// Item number typed so far, waiting for another digit.
+    number: Option<usize>,
+}
+
+fn label_width(label: &str) -> u32 {
+    label.chars().count() as u32 * CHARACTER_WIDTH
+}
+
+fn digit(key: Key) -> Option<usize> {
+    match key {
+        Key::Zero => Some(0),
+        Key::One => Some(1),
+        Key::Two => Some(2),
+        Key::Three => Some(3),
+        Key::Four => Some(4),
+        Key::Five => Some(5),
+        Key::Six => Some(6),
+        Key::Seven => Some(7),
+        Key::Eight => Some(8),
+        Key::Nine => Some(9),
+        _ => None,
     }
+}
 
-    fn draw<D>(&mut self, draw_target: &mut D)
+impl<'a, T: Copy> Menu<'a, T> {
+    pub fn new(items: &'a [Item<'a, T>]) -> Self {
+        let mut levels = heapless::Vec::new();
+        let _ = levels.push(Level {
+            items,
+            label: None,
+            index: 0,
+            top: 0,
+        });
+        Self {
+            levels,
+            marquee: 0,
+            filterable: false,
+            labelled: false,
+            filter: None,
+            last_tap: Instant::MIN,
+            number: None,
+        }
+    }
+
+    /// Lets Hash start filtering the current level by typed letters.
+    pub fn with_filter(mut self) -> Self {
+        self.filterable = true;
+        self
+    }
+
+    /// Draws a [`SoftKeys`] bar below the list.
+    pub fn with_soft_keys(mut self) -> Self {
+        self.labelled = true;
+        self
+    }
+
+    fn level(&self) -> &Level<'a, T> {
+        self.levels.last().unwrap()
+    }
+
+    fn level_mut(&mut self) -> &mut Level<'a, T> {
+        self.levels.last_mut().unwrap()
+    }
+
+    /// Whether `item` starts with the letters typed so far, ignoring case.
+    fn matches(&self, item: &Item<'a, T>) -> bool {
+        let Some(filter) = &self.filter else {
+            return true;
+        };
+        let mut label = item.label().chars();
+        filter
+            .text()
+            .chars()
+            .chain(filter.tentative())
+            .all(|f| label.next().is_some_and(|c| c.eq_ignore_ascii_case(&f)))
+    }
+
+    /// The items of the current level that pass the filter, with their
+    /// indices.
+    fn visible(&self) -> impl Iterator<Item = (usize, &Item<'a, T>)> {
+        self.level()
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| self.matches(item))
+    }
+
+    /// Position of the selection among the visible items.
+    fn row(&self) -> usize {
+        let index = self.level().index;
+        self.visible().position(|(i, _)| i == index).unwrap_or(0)
+    }
+
+    /// Moves the selection onto a visible item after the filter changes.
+    fn refilter(&mut self) {
+        let index = self.level().index;
+        let first = self.visible().next().map(|(i, _)| i);
+        if !self.visible().any(|(i, _)| i == index)
+            && let Some(first) = first
+        {
+            self.level_mut().index = first;
+        }
+        self.level_mut().top = 0;
+        self.marquee = 0;
+    }
+
+    /// Whether a title bar showing the path or filter is drawn above the
+    /// items.
+    fn has_title(&self) -> bool {
+        self.levels.len() > 1 || self.filter.is_some()
+    }
+
+    /// What Select and Cancel do on the current level.
+    fn soft_keys(&self) -> SoftKeys<'static, Action> {
+        let soft_keys = SoftKeys::new().select("Select", Action::Choose);
+        if self.filter.is_some() {
+            soft_keys.cancel("Clear", Action::Erase)
+        } else if self.levels.len() > 1 {
+            soft_keys.cancel("Back", Action::Back)
+        } else {
+            soft_keys
+        }
+    }
+
+    /// The area of the draw target that the items are drawn in.
+    fn list_area(&self, bounding_box: Rectangle) -> Rectangle {
+        let bounding_box = if self.labelled {
+            soft_keys::above(bounding_box)
+        } else {
+            bounding_box
+        };
+        if self.has_title() {
+            Rectangle::new(
+                bounding_box.top_left + Point::new(0, TITLE_HEIGHT as i32),
+                bounding_box.size.saturating_sub(Size::new(0, TITLE_HEIGHT)),
+            )
+        } else {
+            bounding_box
+        }
+    }
+
+    /// Number of items that fit in a draw target of `height` pixels.
+    fn rows(height: u32) -> usize {
+        ((height.saturating_sub(2) / ROW_HEIGHT) as usize).max(1)
+    }
+
+    /// Moves the viewport the least distance that brings the selection into
+    /// view.
+    fn scroll(&mut self, rows: usize) {
+        let row = self.row();
+        let level = self.level_mut();
+        if row < level.top {
+            level.top = row;
+        } else if row >= level.top + rows {
+            level.top = row + 1 - rows;
+        }
+    }
+
+    /// How far the selected label is scrolled left, pausing at either end.
+    fn marquee_offset(&self, overflow: u32) -> u32 {
+        let steps = overflow.div_ceil(MARQUEE_PIXELS);
+        let step = self.marquee % (steps + 2 * MARQUEE_PAUSE);
+        (step.saturating_sub(MARQUEE_PAUSE) * MARQUEE_PIXELS).min(overflow)
+    }
+
+    /// Width of the column of item numbers, including the gap after it.
+    fn number_width(&self) -> u32 {
+        let mut digits = 1;
+        let mut count = self.level().items.len();
+        while count >= 10 {
+            digits += 1;
+            count /= 10;
+        }
+        (digits + 1) * CHARACTER_WIDTH
+    }
+
+    /// Width left for numbers and labels once any scrollbar is drawn.
+    fn text_width(&self, size: Size) -> u32 {
+        if self.visible().count() > Self::rows(size.height) {
+            size.width.saturating_sub(SCROLLBAR_WIDTH + 1)
+        } else {
+            size.width
+        }
+    }
+
+    /// Pixels by which the selected label is wider than the space for it.
+    fn overflow(&self, size: Size) -> u32 {
+        let label_space = self
+            .text_width(size)
+            .saturating_sub(4 + self.number_width());
+        let level = self.level();
+        level.items.get(level.index).map_or(0, |item| {
+            label_width(item.label()).saturating_sub(label_space)
+        })
+    }
+
+    /// Draws the path to the current level, or the letters typed so far
+    /// when filtering, showing only the end once it is wider than the
+    /// screen.
+    fn draw_title<D>(&self, draw_target: &mut D)
     where
         D: DrawTarget<Color = BinaryColor>,
     {
         let bounding_box = draw_target.bounding_box();
-        let top_left = bounding_box.top_left;
+        let title_box = Rectangle::new(
+            bounding_box.top_left,
+            Size::new(bounding_box.size.width, TITLE_HEIGHT),
+        );
+        let _ = title_box
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+            .draw(draw_target);
+
+        let mut title: heapless::String<64> = heapless::String::new();
+        if let Some(filter) = &self.filter {
+            let _ = title.push_str("Find:");
+            for c in filter.text().chars().chain(filter.tentative()) {
+                let _ = title.push(c);
+            }
+        } else {
+            for label in self.levels.iter().filter_map(|level| level.label) {
+                if !title.is_empty() {
+                    let _ = title.push('>');
+                }
+                for c in label.chars() {
+                    let _ = title.push(c);
+                }
+            }
+        }
+        let columns = (bounding_box.size.width.saturating_sub(4) / CHARACTER_WIDTH) as usize;
+        let skip = title.chars().count().saturating_sub(columns);
+        let shown = title
+            .char_indices()
+            .nth(skip)
+            .map_or("", |(i, _)| &title[i..]);
+
+        let _ = Text::with_alignment(
+            shown,
+            bounding_box.top_left + Point::new(2, 8),
+            MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+            Alignment::Left,
+        )
+        .draw(draw_target);
+    }
 
-        let _ = bounding_box
+    fn draw<D>(&mut self, draw_target: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let full_box = draw_target.bounding_box();
+        let _ = full_box
             .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
             .draw(draw_target);
+        if self.has_title() {
+            self.draw_title(draw_target);
+        }
+        if self.labelled {
+            self.soft_keys().draw(draw_target);
+        }
 
-        for (index, item) in self.items.iter().enumerate() {
-            let y_offset: i32 = (index * 10).try_into().unwrap();
-            if self.index == index {
-                let _ = Rectangle::new(
-                    top_left + Point::new(0, y_offset + 2),
-                    Size::new(draw_target.bounding_box().size.width, 11),
-                )
-                .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
-                .draw(draw_target);
+        let list_area = self.list_area(full_box);
+        self.draw_list(&mut draw_target.cropped(&list_area));
+    }
 
-                let _ = Text::with_alignment(
-                    item,
-                    top_left + Point::new(2, 10) + Point::new(0, y_offset),
-                    MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
-                    Alignment::Left,
-                )
-                .draw(draw_target);
+    fn draw_list<D>(&mut self, draw_target: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let bounding_box = draw_target.bounding_box();
+        let top_left = bounding_box.top_left;
+        let rows = Self::rows(bounding_box.size.height);
+        self.scroll(rows);
+
+        let text_width = self.text_width(bounding_box.size);
+        let number_width = self.number_width();
+        let label_space = text_width.saturating_sub(4 + number_width);
+        let level = self.level();
+        let visible = self.visible().count();
+
+        for (row, (index, item)) in self.visible().skip(level.top).take(rows).enumerate() {
+            let y_offset = row as i32 * ROW_HEIGHT as i32;
+            let row_box = Rectangle::new(
+                top_left + Point::new(0, y_offset + 2),
+                Size::new(text_width, ROW_HEIGHT + 1),
+            );
+
+            let color = if level.index == index {
+                let _ = row_box
+                    .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+                    .draw(draw_target);
+                BinaryColor::On
+            } else {
+                BinaryColor::Off
+            };
+            let style = MonoTextStyle::new(&FONT_6X10, color);
+
+            let mut number: heapless::String<4> = heapless::String::new();
+            let _ = write!(number, "{}", index + 1);
+            let _ = Text::with_alignment(
+                &number,
+                top_left + Point::new(2, 10 + y_offset),
+                style,
+                Alignment::Left,
+            )
+            .draw(draw_target);
+
+            let overflow = label_width(item.label()).saturating_sub(label_space);
+            let x_offset = if level.index == index {
+                self.marquee_offset(overflow) as i32
             } else {
-                let _ = Text::with_alignment(
-                    item,
-                    top_left + Point::new(2, 10) + Point::new(0, y_offset),
-                    MonoTextStyle::new(&FONT_6X10, BinaryColor::Off),
-                    Alignment::Left,
-                )
+                0
+            };
+
+            // labels wider than the screen are clipped to their row
+            let label_box = Rectangle::new(
+                row_box.top_left + Point::new(2 + number_width as i32, 0),
+                Size::new(label_space, ROW_HEIGHT + 1),
+            );
+            let _ = Text::with_alignment(
+                item.label(),
+                label_box.top_left + Point::new(-x_offset, 8),
+                style,
+                Alignment::Left,
+            )
+            .draw(&mut draw_target.clipped(&label_box));
+        }
+
+        if visible > rows {
+            let track = Rectangle::new(
+                top_left + Point::new(text_width as i32 + 1, 2),
+                Size::new(SCROLLBAR_WIDTH, bounding_box.size.height.saturating_sub(2)),
+            );
+            let _ = track
+                .into_styled(PrimitiveStyle::with_stroke(BinaryColor::Off, 1))
                 .draw(draw_target);
-            }
+
+            let height = track.size.height;
+            let thumb_height = (height * rows as u32 / visible as u32).max(3);
+            let last_top = visible - rows;
+            let thumb_y = (height - thumb_height) * level.top as u32 / last_top as u32;
+            let _ = Rectangle::new(
+                track.top_left + Point::new(0, thumb_y as i32),
+                Size::new(SCROLLBAR_WIDTH, thumb_height),
+            )
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+            .draw(draw_target);
         }
     }
 
     fn down(&mut self) {
-        self.index = (self.index + 1) % self.items.len()
+        let index = self.level().index;
+        let next = self
+            .visible()
+            .find(|(i, _)| *i > index)
+            .or_else(|| self.visible().next());
+        if let Some((next, _)) = next {
+            self.level_mut().index = next;
+        }
+        self.marquee = 0;
     }
 
     fn up(&mut self) {
-        if self.index == 0 {
-            self.index = self.items.len() - 1;
+        let index = self.level().index;
+        let previous = self
+            .visible()
+            .filter(|(i, _)| *i < index)
+            .last()
+            .or_else(|| self.visible().last());
+        if let Some((previous, _)) = previous {
+            self.level_mut().index = previous;
+        }
+        self.marquee = 0;
+    }
+
+    /// Chooses the selected item, returning its identifier if it is a leaf
+    /// or opening it if it is a submenu.
+    fn select(&mut self) -> Option<T> {
+        let level = self.level();
+        let item = level.items.get(level.index)?;
+        if !self.matches(item) {
+            return None;
+        }
+        match item {
+            Item::Leaf { id, .. } => Some(*id),
+            Item::Submenu { label, items } => {
+                if items.is_empty() {
+                    return None;
+                }
+                let opened = Level {
+                    items,
+                    label: Some(label),
+                    index: 0,
+                    top: 0,
+                };
+                if self.levels.push(opened).is_err() {
+                    log::warn!("menu nested deeper than {}", MAX_DEPTH);
+                }
+                self.filter = None;
+                self.marquee = 0;
+                None
+            }
+        }
+    }
+
+    /// Selects and chooses item `number`, counting from 1.
+    fn shortcut(&mut self, number: usize) -> Option<T> {
+        if number == 0 || number > self.level().items.len() {
+            return None;
+        }
+        self.level_mut().index = number - 1;
+        self.select()
+    }
+
+    /// Returns to the parent of the current level.  Does nothing at the top
+    /// level.
+    fn back(&mut self) {
+        if self.levels.len() > 1 {
+            self.levels.pop();
+            self.marquee = 0;
+        }
+    }
+
+    /// Draws the menu and waits for the next key, scrolling the marquee
+    /// meanwhile.
+    async fn event<KEYPAD, D>(&mut self, keypad: &mut KEYPAD, draw_target: &mut D) -> KeyEvent
+    where
+        KEYPAD: Keypad,
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.draw(draw_target);
+        let list_area = self.list_area(draw_target.bounding_box());
+        if self.overflow(list_area.size) > 0 {
+            loop {
+                match with_timeout(MARQUEE_STEP, keypad.event()).await {
+                    Ok(event) => break event,
+                    Err(_) => {
+                        self.marquee = self.marquee.wrapping_add(1);
+                        self.draw(draw_target);
+                    }
+                }
+            }
         } else {
-            self.index -= 1;
+            keypad.event().await
+        }
+    }
+
+    /// Adds `digit` to the item `number` typed so far, choosing the item once
+    /// no more digits could follow.
+    fn type_digit(&mut self, number: Option<usize>, digit: usize) -> Option<T> {
+        let number = number.unwrap_or(0) * 10 + digit;
+        if number * 10 > self.level().items.len() {
+            self.shortcut(number)
+        } else {
+            if number > 0 {
+                self.number = Some(number);
+            }
+            None
         }
     }
 
@@ -74,24 +549,300 @@ impl<'a> Menu<'a> {
         &mut self,
         keypad: &mut KEYPAD,
         draw_target: &mut D,
-    ) -> Option<usize>
+    ) -> Option<T>
     where
         KEYPAD: Keypad,
         D: DrawTarget<Color = BinaryColor>,
     {
-        self.draw(draw_target);
-        match keypad.event().await {
-            super::KeyEvent::Down(super::Key::Down) => {
+        let event = match self.number {
+            Some(number) => {
+                match with_timeout(SHORTCUT_TIMEOUT, self.event(keypad, draw_target)).await {
+                    Ok(event) => event,
+                    Err(_) => {
+                        self.number = None;
+                        return self.shortcut(number);
+                    }
+                }
+            }
+            None => self.event(keypad, draw_target).await,
+        };
+        // a press of anything but another digit ends the number
+        let number = match event {
+            KeyEvent::Down(_) => self.number.take(),
+            _ => None,
+        };
+
+        if let Some(action) = self.soft_keys().resolve(event) {
+            match action {
+                Action::Choose => return self.select(),
+                Action::Back => self.back(),
+                Action::Erase => {
+                    if let Some(filter) = &mut self.filter
+                        && !filter.backspace()
+                    {
+                        self.filter = None;
+                    }
+                    self.refilter();
+                }
+            }
+            return None;
+        }
+
+        match event {
+            KeyEvent::Down(Key::Down) | KeyEvent::Repeat(Key::Down) => {
                 self.down();
                 None
             }
-            super::KeyEvent::Down(super::Key::Up) => {
+            KeyEvent::Down(Key::Up) | KeyEvent::Repeat(Key::Up) => {
                 self.up();
                 None
             }
-
-            super::KeyEvent::Down(super::Key::Select) => Some(self.index),
+            KeyEvent::Down(Key::Select | Key::Cancel) => None,
+            KeyEvent::Down(Key::Hash) if self.filterable && self.filter.is_none() => {
+                self.filter = Some(Input::new());
+                None
+            }
+            KeyEvent::Down(key) => match &mut self.filter {
+                Some(filter) => {
+                    let now = Instant::now();
+                    if now - self.last_tap >= TAP_TIMEOUT {
+                        filter.decide();
+                    }
+                    self.last_tap = now;
+                    filter.press(key);
+                    self.refilter();
+                    None
+                }
+                None => self.type_digit(number, digit(key)?),
+            },
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    const fn leaf(label: &'static str, id: usize) -> Item<'static, usize> {
+        Item::Leaf { label, id }
+    }
+
+    const ITEMS: &[Item<usize>] = &[
+        leaf("One", 0),
+        leaf("Two", 1),
+        leaf("Three", 2),
+        leaf("Four", 3),
+        leaf("Five", 4),
+        leaf("Six", 5),
+    ];
+
+    const TREE: &[Item<&str>] = &[
+        Item::Submenu {
+            label: "Settings",
+            items: &[
+                Item::Submenu {
+                    label: "Display",
+                    items: &[Item::Leaf {
+                        label: "Contrast",
+                        id: "contrast",
+                    }],
+                },
+                Item::Leaf {
+                    label: "Tones",
+                    id: "tones",
+                },
+            ],
+        },
+        Item::Leaf {
+            label: "Clock",
+            id: "clock",
+        },
+    ];
+
+    fn press<T: Copy>(menu: &mut Menu<T>, display: &mut fake::Display, key: Key) -> Option<T> {
+        let mut keypad = fake::Keypad::default();
+        keypad.push(KeyEvent::Down(key));
+        embassy_futures::block_on(menu.process(&mut keypad, display))
+    }
+
+    #[test]
+    fn test_viewport_follows_selection() {
+        let mut menu = Menu::new(ITEMS);
+        let mut display = fake::Display::default();
+
+        for _ in 0..5 {
+            press(&mut menu, &mut display, Key::Down);
+        }
+        assert_eq!(press(&mut menu, &mut display, Key::Select), Some(5));
+        assert_eq!(menu.level().top, 2);
+
+        press(&mut menu, &mut display, Key::Down);
+        press(&mut menu, &mut display, Key::Select);
+        assert_eq!(menu.level().top, 0);
+    }
+
+    #[test]
+    fn test_scrollbar() {
+        let mut display = fake::Display::default();
+        let track = Point::new(fake::WIDTH as i32 - 1, 30);
+
+        press(&mut Menu::new(&ITEMS[..4]), &mut display, Key::Select);
+        assert_eq!(display.pixel(track), Some(BinaryColor::On));
+
+        press(&mut Menu::new(ITEMS), &mut display, Key::Select);
+        assert_eq!(display.pixel(track), Some(BinaryColor::Off));
+    }
+
+    #[test]
+    fn test_marquee_offset() {
+        const WIDE: &[Item<usize>] = &[leaf("A label that is far too wide", 0)];
+        let mut menu = Menu::new(WIDE);
+        let overflow = menu.overflow(Size::new(fake::WIDTH as u32, fake::HEIGHT as u32));
+        assert!(overflow > 0);
+
+        assert_eq!(menu.marquee_offset(overflow), 0);
+        menu.marquee = MARQUEE_PAUSE + 2;
+        assert_eq!(menu.marquee_offset(overflow), 2 * MARQUEE_PIXELS);
+        menu.marquee = MARQUEE_PAUSE + overflow.div_ceil(MARQUEE_PIXELS);
+        assert_eq!(menu.marquee_offset(overflow), overflow);
+    }
+
+    #[test]
+    fn test_submenus() {
+        let mut menu = Menu::new(TREE);
+        let mut display = fake::Display::default();
+
+        assert_eq!(press(&mut menu, &mut display, Key::Select), None);
+        assert_eq!(press(&mut menu, &mut display, Key::Select), None);
+        assert_eq!(
+            press(&mut menu, &mut display, Key::Select),
+            Some("contrast")
+        );
+
+        press(&mut menu, &mut display, Key::Cancel);
+        press(&mut menu, &mut display, Key::Down);
+        assert_eq!(press(&mut menu, &mut display, Key::Select), Some("tones"));
+
+        press(&mut menu, &mut display, Key::Cancel);
+        press(&mut menu, &mut display, Key::Cancel);
+        press(&mut menu, &mut display, Key::Down);
+        assert_eq!(press(&mut menu, &mut display, Key::Select), Some("clock"));
+    }
+
+    #[test]
+    fn test_title_bar() {
+        let mut menu = Menu::new(TREE);
+        let mut display = fake::Display::default();
+        let title = Point::new(fake::WIDTH as i32 - 1, 0);
+
+        // each press draws the menu as it was before the key is handled
+        press(&mut menu, &mut display, Key::Select);
+        assert_eq!(display.pixel(title), Some(BinaryColor::On));
+
+        press(&mut menu, &mut display, Key::Cancel);
+        assert_eq!(display.pixel(title), Some(BinaryColor::Off));
+
+        press(&mut menu, &mut display, Key::Up);
+        assert_eq!(display.pixel(title), Some(BinaryColor::On));
+    }
+
+    #[test]
+    fn test_shortcuts() {
+        let mut menu = Menu::new(TREE);
+        let mut display = fake::Display::default();
+
+        assert_eq!(press(&mut menu, &mut display, Key::One), None);
+        assert_eq!(press(&mut menu, &mut display, Key::One), None);
+        assert_eq!(press(&mut menu, &mut display, Key::One), Some("contrast"));
+
+        press(&mut menu, &mut display, Key::Cancel);
+        assert_eq!(press(&mut menu, &mut display, Key::Two), Some("tones"));
+        assert_eq!(press(&mut menu, &mut display, Key::Nine), None);
+
+        press(&mut menu, &mut display, Key::Cancel);
+        assert_eq!(press(&mut menu, &mut display, Key::Two), Some("clock"));
+    }
+
+    #[test]
+    fn test_long_shortcuts() {
+        const LONG: &[Item<usize>] = &[
+            leaf("1", 0),
+            leaf("2", 1),
+            leaf("3", 2),
+            leaf("4", 3),
+            leaf("5", 4),
+            leaf("6", 5),
+            leaf("7", 6),
+            leaf("8", 7),
+            leaf("9", 8),
+            leaf("10", 9),
+            leaf("11", 10),
+            leaf("12", 11),
+        ];
+        let mut menu = Menu::new(LONG);
+        let mut display = fake::Display::default();
+
+        assert_eq!(press(&mut menu, &mut display, Key::One), None);
+        assert_eq!(press(&mut menu, &mut display, Key::Two), Some(11));
+        assert_eq!(press(&mut menu, &mut display, Key::One), None);
+        assert_eq!(press(&mut menu, &mut display, Key::Zero), Some(9));
+        assert_eq!(press(&mut menu, &mut display, Key::Three), Some(2));
+        assert_eq!(press(&mut menu, &mut display, Key::Zero), None);
+
+        // without another digit, 1 alone chooses the first item
+        assert_eq!(press(&mut menu, &mut display, Key::One), None);
+        let mut keypad = fake::Keypad::default();
+        assert_eq!(
+            embassy_futures::block_on(menu.process(&mut keypad, &mut display)),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_soft_keys() {
+        let mut menu = Menu::new(TREE).with_soft_keys();
+        let mut display = fake::Display::default();
+        let bar = soft_keys::above(display.bounding_box())
+            .bottom_right()
+            .unwrap()
+            .y
+            + 1;
+        let labelled = |display: &fake::Display, x: core::ops::Range<i32>| {
+            x.flat_map(|x| (bar..fake::HEIGHT as i32).map(move |y| Point::new(x, y)))
+                .any(|point| display.pixel(point) == Some(BinaryColor::Off))
+        };
+
+        assert_eq!(
+            Menu::<&str>::rows(menu.list_area(display.bounding_box()).size.height),
+            3
+        );
+        press(&mut menu, &mut display, Key::Select);
+        assert!(labelled(&display, 30..54));
+        assert!(!labelled(&display, 66..fake::WIDTH as i32));
+
+        press(&mut menu, &mut display, Key::Down);
+        assert!(labelled(&display, 66..fake::WIDTH as i32));
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut menu = Menu::new(ITEMS).with_filter();
+        let mut display = fake::Display::default();
+
+        press(&mut menu, &mut display, Key::Hash);
+        for _ in 0..3 {
+            press(&mut menu, &mut display, Key::Three);
+        }
+        assert_eq!(menu.visible().count(), 2);
+        press(&mut menu, &mut display, Key::Down);
+        assert_eq!(press(&mut menu, &mut display, Key::Select), Some(4));
+
+        press(&mut menu, &mut display, Key::Cancel);
+        assert_eq!(menu.visible().count(), ITEMS.len());
+        press(&mut menu, &mut display, Key::Cancel);
+        assert!(menu.filter.is_none());
+        assert_eq!(press(&mut menu, &mut display, Key::One), Some(0));
+    }
+}
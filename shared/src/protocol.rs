@@ -0,0 +1,355 @@
+//! Framed messages exchanged with the host over USB CDC.
+//!
+//! Each [`Message`] is laid out as a little endian `u16` length, a tag byte
+//! and a body of that length, followed by a big endian CRC-16/CCITT-FALSE of
+//! everything before it.  The result is COBS encoded and terminated with a
+//! zero byte, so a frame can span any number of USB packets and the
+//! [`Decoder`] can resynchronise after garbage by waiting for the next zero.
+
+/// Largest [`Message::Data`] payload.
+pub const MAX_DATA: usize = 128;
+/// Length, tag, body and checksum.
+const MAX_PAYLOAD: usize = 2 + 1 + MAX_DATA + 2;
+/// Longest encoded frame, including COBS overhead and the terminating zero.
+pub const MAX_FRAME: usize = MAX_PAYLOAD + MAX_PAYLOAD / 254 + 2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    Ping,
+    Pong,
+    /// The request was carried out.
+    Ack,
+    /// The request was understood but could not be carried out.
+    Nack,
+    GetTime,
+    /// The RTC time as a unix timestamp.
+    Time(i64),
+    SetTime(i64),
+    /// Anything else, for the running app to interpret.
+    Data(heapless::Vec<u8, MAX_DATA>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The frame or message does not fit in the buffer.
+    Overflow,
+    /// The frame is not valid COBS.
+    Encoding,
+    Checksum,
+    /// The length prefix does not match the frame.
+    Length,
+    UnknownTag(u8),
+}
+
+const PING: u8 = 0x01;
+const PONG: u8 = 0x02;
+const ACK: u8 = 0x03;
+const NACK: u8 = 0x04;
+const GET_TIME: u8 = 0x10;
+const TIME: u8 = 0x11;
+const SET_TIME: u8 = 0x12;
+const DATA: u8 = 0x20;
+
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0xffff;
+    for byte in bytes {
+        crc ^= u16::from(*byte) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// COBS encodes `input` into `output`, returning the encoded length.  Does not
+/// add the terminating zero.
+fn cobs_encode(input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+    let mut code_index = 0;
+    let mut index = 1;
+    let mut code: u8 = 1;
+
+    for byte in input {
+        if *byte == 0 {
+            *output.get_mut(code_index).ok_or(Error::Overflow)? = code;
+            code_index = index;
+            index += 1;
+            code = 1;
+        } else {
+            *output.get_mut(index).ok_or(Error::Overflow)? = *byte;
+            index += 1;
+            code += 1;
+            if code == 0xff {
+                *output.get_mut(code_index).ok_or(Error::Overflow)? = code;
+                code_index = index;
+                index += 1;
+                code = 1;
+            }
+        }
+    }
+    *output.get_mut(code_index).ok_or(Error::Overflow)? = code;
+
+    Ok(index)
+}
+
+/// Reverses [`cobs_encode`], returning the decoded length.
+fn cobs_decode(input: &[u8], output: &mut [u8]) -> Result<usize, Error> {
+    let mut input = input.iter();
+    let mut index = 0;
+
+    while let Some(code) = input.next() {
+        if *code == 0 {
+            return Err(Error::Encoding);
+        }
+        for _ in 1..*code {
+            match input.next() {
+                Some(0) | None => return Err(Error::Encoding),
+                Some(byte) => {
+                    *output.get_mut(index).ok_or(Error::Overflow)? = *byte;
+                    index += 1;
+                }
+            }
+        }
+        if *code != 0xff && input.len() > 0 {
+            *output.get_mut(index).ok_or(Error::Overflow)? = 0;
+            index += 1;
+        }
+    }
+
+    Ok(index)
+}
+
+impl Message {
+    fn tag(&self) -> u8 {
+        match self {
+            Message::Ping => PING,
+            Message::Pong => PONG,
+            Message::Ack => ACK,
+            Message::Nack => NACK,
+            Message::GetTime => GET_TIME,
+            Message::Time(_) => TIME,
+            Message::SetTime(_) => SET_TIME,
+            Message::Data(_) => DATA,
+        }
+    }
+
+    fn body(&self, buffer: &mut [u8; 8]) -> usize {
+        match self {
+            Message::Time(timestamp) | Message::SetTime(timestamp) => {
+                *buffer = timestamp.to_le_bytes();
+                8
+            }
+            _ => 0,
+        }
+    }
+
+    /// Encodes the message as a complete frame, terminating zero included,
+    /// returning the number of bytes written.
+    pub fn encode(&self, frame: &mut [u8]) -> Result<usize, Error> {
+        let mut fixed = [0; 8];
+        let body: &[u8] = match self {
+            Message::Data(data) => data,
+            _ => {
+                let length = self.body(&mut fixed);
+                &fixed[..length]
+            }
+        };
+
+        let mut payload: heapless::Vec<u8, MAX_PAYLOAD> = heapless::Vec::new();
+        let length = u16::try_from(body.len()).map_err(|_| Error::Overflow)?;
+        payload
+            .extend_from_slice(&length.to_le_bytes())
+            .map_err(|_| Error::Overflow)?;
+        payload.push(self.tag()).map_err(|_| Error::Overflow)?;
+        payload
+            .extend_from_slice(body)
+            .map_err(|_| Error::Overflow)?;
+        let crc = crc16(&payload);
+        payload
+            .extend_from_slice(&crc.to_be_bytes())
+            .map_err(|_| Error::Overflow)?;
+
+        let length = cobs_encode(&payload, frame)?;
+        *frame.get_mut(length).ok_or(Error::Overflow)? = 0;
+        Ok(length + 1)
+    }
+
+    /// Decodes a frame without its terminating zero.
+    pub fn decode(frame: &[u8]) -> Result<Self, Error> {
+        let mut buffer = [0; MAX_PAYLOAD];
+        let length = cobs_decode(frame, &mut buffer)?;
+        let payload = &buffer[..length];
+
+        let (payload, crc) = payload.split_last_chunk::<2>().ok_or(Error::Length)?;
+        if crc16(payload) != u16::from_be_bytes(*crc) {
+            return Err(Error::Checksum);
+        }
+        let (header, body) = payload.split_first_chunk::<3>().ok_or(Error::Length)?;
+        let [length_low, length_high, tag] = *header;
+        if usize::from(u16::from_le_bytes([length_low, length_high])) != body.len() {
+            return Err(Error::Length);
+        }
+
+        let timestamp = || -> Result<i64, Error> {
+            Ok(i64::from_le_bytes(
+                body.try_into().map_err(|_| Error::Length)?,
+            ))
+        };
+        let empty = |message: Message| {
+            if body.is_empty() {
+                Ok(message)
+            } else {
+                Err(Error::Length)
+            }
+        };
+
+        match tag {
+            PING => empty(Message::Ping),
+            PONG => empty(Message::Pong),
+            ACK => empty(Message::Ack),
+            NACK => empty(Message::Nack),
+            GET_TIME => empty(Message::GetTime),
+            TIME => Ok(Message::Time(timestamp()?)),
+            SET_TIME => Ok(Message::SetTime(timestamp()?)),
+            DATA => Ok(Message::Data(
+                heapless::Vec::from_slice(body).map_err(|_| Error::Overflow)?,
+            )),
+            tag => Err(Error::UnknownTag(tag)),
+        }
+    }
+}
+
+/// Reassembles frames from a byte stream, however it is split into packets.
+#[derive(Default)]
+pub struct Decoder {
+    frame: heapless::Vec<u8, MAX_FRAME>,
+    overflowed: bool,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds in the next byte, returning the decoded message when it ends a
+    /// frame.  An error only affects its own frame.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Message, Error>> {
+        if byte != 0 {
+            if self.frame.push(byte).is_err() {
+                self.overflowed = true;
+            }
+            return None;
+        }
+
+        let result = if self.overflowed {
+            Err(Error::Overflow)
+        } else if self.frame.is_empty() {
+            return None;
+        } else {
+            Message::decode(&self.frame)
+        };
+        self.frame.clear();
+        self.overflowed = false;
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(message: Message) {
+        let mut frame = [0; MAX_FRAME];
+        let length = message.encode(&mut frame).unwrap();
+        let frame = &frame[..length];
+
+        assert_eq!(frame.last(), Some(&0));
+        assert!(!frame[..length - 1].contains(&0));
+        assert_eq!(Message::decode(&frame[..length - 1]), Ok(message));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(Message::Ping);
+        round_trip(Message::Pong);
+        round_trip(Message::Ack);
+        round_trip(Message::Nack);
+        round_trip(Message::GetTime);
+        round_trip(Message::Time(1_700_000_000));
+        round_trip(Message::SetTime(-1));
+        round_trip(Message::Data(heapless::Vec::new()));
+        round_trip(Message::Data(
+            heapless::Vec::from_slice(&[0; MAX_DATA]).unwrap(),
+        ));
+    }
+
+    #[test]
+    fn test_crc16() {
+        assert_eq!(crc16(b"123456789"), 0x29b1);
+    }
+
+    #[test]
+    fn test_cobs_long_run() {
+        let input = [7; 600];
+        let mut encoded = [0; 610];
+        let mut decoded = [0; 600];
+
+        let length = cobs_encode(&input, &mut encoded).unwrap();
+        assert!(!encoded[..length].contains(&0));
+        assert_eq!(cobs_decode(&encoded[..length], &mut decoded), Ok(600));
+        assert_eq!(decoded, input);
+    }
+
+    #[test]
+    fn test_decoder_across_packets() {
+        let data = Message::Data((0..MAX_DATA as u8).collect());
+        let mut stream: heapless::Vec<u8, { 3 * MAX_FRAME }> = heapless::Vec::new();
+        let mut frame = [0; MAX_FRAME];
+        for message in [&Message::Ping, &data, &Message::Time(42)] {
+            let length = message.encode(&mut frame).unwrap();
+            stream.extend_from_slice(&frame[..length]).unwrap();
+        }
+
+        let mut decoder = Decoder::new();
+        let mut decoded: heapless::Vec<Message, 3> = heapless::Vec::new();
+        for packet in stream.chunks(64) {
+            for byte in packet {
+                if let Some(result) = decoder.push(*byte) {
+                    decoded.push(result.unwrap()).unwrap();
+                }
+            }
+        }
+
+        assert_eq!(decoded, [Message::Ping, data, Message::Time(42)]);
+    }
+
+    #[test]
+    fn test_decoder_recovers() {
+        let mut frame = [0; MAX_FRAME];
+        let mut decoder = Decoder::new();
+
+        let length = Message::Time(42).encode(&mut frame).unwrap();
+        frame[3] ^= 0x10;
+        let results: heapless::Vec<_, 2> = frame[..length]
+            .iter()
+            .filter_map(|byte| decoder.push(*byte))
+            .collect();
+        assert_eq!(results, [Err(Error::Checksum)]);
+
+        for _ in 0..MAX_FRAME + 1 {
+            assert_eq!(decoder.push(1), None);
+        }
+        assert_eq!(decoder.push(0), Some(Err(Error::Overflow)));
+
+        let length = Message::Pong.encode(&mut frame).unwrap();
+        let results: heapless::Vec<_, 2> = frame[..length]
+            .iter()
+            .filter_map(|byte| decoder.push(*byte))
+            .collect();
+        assert_eq!(results, [Ok(Message::Pong)]);
+    }
+}
@@ -0,0 +1,271 @@
+//! Declarative list of the applications a target ships with.  The list
+//! itself lives in the `applications` crate, which depends on each app.
+//!
+//! Applications are not object safe (`run` is generic over the board), so
+//! [`registry!`](crate::registry) expands to a type whose
+//! [`Registry::launch`] dispatches on the index chosen in the [`Launcher`].
+
+use core::{future::Future, marker::PhantomData};
+
+use embassy_futures::select::{Either4, select4};
+use embedded_graphics::{image::ImageRaw, pixelcolor::BinaryColor};
+
+use crate::{
+    Board, Peripherals,
+    backlight::{self, Waking},
+    keyguard::{self, Guarded},
+    keypad::{Clicks, KeyTones},
+    menu::{Item, Menu},
+    settings::Settings,
+    system::System,
+};
+
+pub struct Entry {
+    pub name: &'static str,
+    /// Set with `icon` in [`registry!`](crate::registry), if the app has one.
+    pub icon: Option<ImageRaw<'static, BinaryColor>>,
+}
+
+pub trait Registry {
+    const ENTRIES: &'static [Entry];
+    const NAMES: &'static [&'static str];
+    /// One leaf per entry, identified by its name.
+    const MENU: &'static [Item<'static, &'static str>];
+
+    /// Constructs the application at `index` and runs it until it exits,
+    /// returning the name of the app it asked to launch next, if any.
+    fn launch(
+        index: usize,
+        board: &mut impl Board,
+        system: &mut impl System,
+    ) -> impl Future<Output = Option<&'static str>>;
+
+    fn position(name: &str) -> Option<usize> {
+        Self::NAMES.iter().position(|n| *n == name)
+    }
+}
+
+/// Declares a [`Registry`] named `$registry`.
+///
+/// ```ignore
+/// shared::registry! {
+///     pub Applications {
+///         "Clock" => clock::Clock::new(),
+///         "Snake" icon SNAKE_ICON => snake::Snake::default(),
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! registry {
+    (@icon) => {
+        None
+    };
+    (@icon $icon:expr) => {
+        Some($icon)
+    };
+    (
+        $vis:vis $registry:ident {
+            $($name:literal $(icon $icon:expr)? => $constructor:expr),* $(,)?
+        }
+    ) => {
+        $vis struct $registry;
+
+        impl $crate::registry::Registry for $registry {
+            const ENTRIES: &'static [$crate::registry::Entry] = &[$(
+                $crate::registry::Entry {
+                    name: $name,
+                    icon: $crate::registry!(@icon $($icon)?),
+                }
+            ),*];
+            const NAMES: &'static [&'static str] = &[$($name),*];
+            const MENU: &'static [$crate::menu::Item<'static, &'static str>] = &[$(
+                $crate::menu::Item::Leaf { label: $name, id: $name }
+            ),*];
+
+            async fn launch(
+                index: usize,
+                board: &mut impl $crate::Board,
+                system: &mut impl $crate::system::System,
+            ) -> Option<&'static str> {
+                let mut _i = 0;
+                $(
+                    if index == _i {
+                        return $crate::run_app($constructor, board, system).await;
+                    }
+                    _i += 1;
+                )*
+                None
+            }
+        }
+    };
+}
+
+/// Top level menu listing every application in a [`Registry`].
+pub struct Launcher<R: Registry> {
+    menu: Menu<'static, &'static str>,
+    registry: PhantomData<R>,
+}
+
+impl<R: Registry> Default for Launcher<R> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<R: Registry> Launcher<R> {
+    pub fn new() -> Self {
+        Self {
+            menu: Menu::new(R::MENU).with_filter().with_soft_keys(),
+            registry: PhantomData,
+        }
+    }
+
+    /// Shows the menu until an application is chosen, returning its index.
+    /// Keys go through the system's keyguard and wake the backlight, as they
+    /// do for apps.
+    pub async fn select(&mut self, board: &mut impl Board, system: &mut impl System) -> usize {
+        let key_tones = system.settings().key_clicks();
+        system.backlight().release();
+        loop {
+            let keyguard = system.keyguard();
+            let timeout = system.backlight();
+            let Peripherals {
+                keypad,
+                buzzer,
+                display,
+                backlight,
+                ..
+            } = board.peripherals();
+            let clicks = Clicks::new();
+            let mut keypad = Waking::new(Guarded::new(keypad, keyguard), timeout);
+            let mut keypad = KeyTones::new(&mut keypad, &clicks, key_tones);
+            match select4(
+                self.menu.process(&mut keypad, display),
+                keyguard.notice(),
+                timeout.drive(backlight),
+                clicks.play(buzzer),
+            )
+            .await
+            {
+                Either4::First(Some(name)) => {
+                    if let Some(index) = R::position(name) {
+                        return index;
+                    }
+                }
+                Either4::First(None) => {}
+                Either4::Second(notice) => {
+                    keyguard::show(notice, board, keyguard).await;
+                    backlight::after_notice(system);
+                }
+                Either4::Third(never) | Either4::Fourth(never) => match never {},
+            }
+        }
+    }
+
+    /// Launches `index` and then any apps it asks for in turn, until one
+    /// exits without asking for another.
+    pub async fn launch(&mut self, index: usize, board: &mut impl Board, system: &mut impl System) {
+        let mut next = R::launch(index, board, system).await;
+        while let Some(name) = next {
+            match R::position(name) {
+                Some(index) => next = R::launch(index, board, system).await,
+                None => {
+                    log::warn!("no app named {}", name);
+                    next = None;
+                }
+            }
+        }
+    }
+
+    /// Restores the [`Settings`] and then shows the menu and launches
+    /// whatever is chosen, forever.
+    pub async fn run(&mut self, board: &mut impl Board, system: &mut impl System) {
+        let settings = Settings::load(board.storage()).await;
+        settings.apply(board);
+        system.backlight().set_timeout(settings.backlight_timeout);
+        system.backlight().wake(embassy_time::Instant::now());
+        *system.settings() = settings;
+
+        loop {
+            let index = self.select(board, system).await;
+            self.launch(index, board, system).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embassy_time::Instant;
+
+    use super::*;
+    use crate::{
+        Application, Backlight, Key, KeyEvent, SystemRequest, SystemResponse, VibrationMotor, fake,
+    };
+
+    struct Buzz;
+
+    impl Application for Buzz {
+        async fn run(
+            &mut self,
+            board: &mut impl Board,
+            _system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            board.vibration_motor().start();
+            Some(SystemRequest::Launch("Quiet"))
+        }
+    }
+
+    struct Quiet;
+
+    impl Application for Quiet {
+        async fn run(
+            &mut self,
+            board: &mut impl Board,
+            _system_response: Option<SystemResponse>,
+        ) -> Option<SystemRequest> {
+            board.backlight().on();
+            Some(SystemRequest::Launch("Missing"))
+        }
+    }
+
+    const ICON: ImageRaw<'static, BinaryColor> = ImageRaw::new(&[0x3c, 0x42, 0x42, 0x3c], 8);
+
+    crate::registry! {
+        Test {
+            "Quiet" icon ICON => Quiet,
+            "Buzz" => Buzz,
+        }
+    }
+
+    #[test]
+    fn test_entries() {
+        assert_eq!(Test::NAMES, &["Quiet", "Buzz"]);
+        assert_eq!(Test::position("Buzz"), Some(1));
+        assert_eq!(Test::ENTRIES.len(), 2);
+        assert_eq!(Test::ENTRIES[1].name, "Buzz");
+        assert!(Test::ENTRIES[0].icon.is_some());
+        assert!(Test::ENTRIES[1].icon.is_none());
+        assert_eq!(Test::MENU[1].label(), "Buzz");
+    }
+
+    #[test]
+    fn test_select_and_launch() {
+        let mut board = fake::Board::default();
+        board.keypad.push(KeyEvent::Down(Key::Down));
+        board.keypad.push(KeyEvent::Down(Key::Select));
+        let mut launcher = Launcher::<Test>::new();
+
+        let mut system = fake::System::default();
+
+        let index = embassy_futures::block_on(launcher.select(&mut board, &mut system));
+        assert_eq!(index, 1);
+        assert!(board.backlight.on, "keys should light the backlight");
+
+        embassy_futures::block_on(launcher.launch(index, &mut board, &mut system));
+        assert!(
+            system.backlight.off_at(Instant::now()).is_none(),
+            "Buzz should have launched Quiet, which holds the backlight on"
+        );
+        assert!(!board.vibration_motor.running);
+    }
+}
@@ -0,0 +1,188 @@
+//! Device preferences, kept in [`Storage`] and applied to the board.
+//!
+//! Apps change a preference by returning
+//! [`SystemRequest::SaveSetting`](crate::SystemRequest::SaveSetting) with one
+//! of the keys below, and the system stores and applies it straight away.
+
+use crate::{
+    Board, Buzzer, Display, Storage,
+    time_zone::{TimeZone, ZONES},
+};
+
+pub const CONTRAST: &str = "contrast";
+pub const BACKLIGHT_TIMEOUT: &str = "backlight";
+pub const KEY_TONES: &str = "key_tones";
+pub const VIBRATION: &str = "vibration";
+pub const VOLUME: &str = "volume";
+pub const SILENT: &str = "silent";
+pub const CLOCK_24H: &str = "clock_24h";
+pub const TIME_ZONE: &str = "time_zone";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// Percent, from faintest to darkest.
+    pub contrast: u8,
+    /// Seconds without a key press before the backlight goes off, or 0 to
+    /// leave it on.
+    pub backlight_timeout: u16,
+    /// Whether keys click in the system's menus and dialogs.
+    pub key_tones: bool,
+    /// Whether apps can start the vibration motor.
+    pub vibration: bool,
+    /// Percent.
+    pub volume: u8,
+    /// Whether the buzzer stays quiet, key tones and all.
+    pub silent: bool,
+    pub clock_24h: bool,
+    /// Index into [`ZONES`].
+    pub time_zone: u8,
+}
+
+impl Settings {
+    pub const DEFAULT: Self = Self {
+        contrast: 50,
+        backlight_timeout: 15,
+        key_tones: true,
+        vibration: true,
+        volume: 50,
+        silent: false,
+        clock_24h: true,
+        time_zone: 0,
+    };
+
+    /// The value stored under `key`, if `key` is a setting.
+    pub fn get(&self, key: &str) -> Option<i32> {
+        Some(match key {
+            CONTRAST => self.contrast.into(),
+            BACKLIGHT_TIMEOUT => self.backlight_timeout.into(),
+            KEY_TONES => self.key_tones.into(),
+            VIBRATION => self.vibration.into(),
+            VOLUME => self.volume.into(),
+            SILENT => self.silent.into(),
+            CLOCK_24H => self.clock_24h.into(),
+            TIME_ZONE => self.time_zone.into(),
+            _ => return None,
+        })
+    }
+
+    /// Sets `key` to `value`, returning `false` if `key` is not a setting or
+    /// `value` is out of its range.
+    pub fn set(&mut self, key: &str, value: i32) -> bool {
+        let percent = u8::try_from(value).ok().filter(|value| *value <= 100);
+        let flag = match value {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        };
+        match (key, percent, flag) {
+            (CONTRAST, Some(percent), _) => self.contrast = percent,
+            (VOLUME, Some(percent), _) => self.volume = percent,
+            (KEY_TONES, _, Some(flag)) => self.key_tones = flag,
+            (VIBRATION, _, Some(flag)) => self.vibration = flag,
+            (SILENT, _, Some(flag)) => self.silent = flag,
+            (CLOCK_24H, _, Some(flag)) => self.clock_24h = flag,
+            (TIME_ZONE, ..) => match u8::try_from(value) {
+                Ok(index) if usize::from(index) < ZONES.len() => self.time_zone = index,
+                _ => return false,
+            },
+            (BACKLIGHT_TIMEOUT, ..) => match u16::try_from(value) {
+                Ok(seconds) => self.backlight_timeout = seconds,
+                Err(_) => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    /// Reads every setting from `storage`, leaving any missing or unreadable
+    /// at its default.
+    pub async fn load(storage: &mut impl Storage) -> Self {
+        let mut settings = Self::DEFAULT;
+        for key in [
+            CONTRAST,
+            BACKLIGHT_TIMEOUT,
+            KEY_TONES,
+            VIBRATION,
+            VOLUME,
+            SILENT,
+            CLOCK_24H,
+            TIME_ZONE,
+        ] {
+            match storage.get::<i32>(key).await {
+                Ok(Some(value)) => {
+                    if !settings.set(key, value) {
+                        log::warn!("ignoring stored {} of {}", key, value);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!("could not read {}: {:?}", key, e),
+            }
+        }
+        settings
+    }
+
+    /// Whether keys click, which they never do while silent.
+    pub fn key_clicks(&self) -> bool {
+        self.key_tones && !self.silent
+    }
+
+    pub fn time_zone(&self) -> TimeZone {
+        ZONES
+            .get(usize::from(self.time_zone))
+            .map_or(TimeZone::UTC, |(_, time_zone)| *time_zone)
+    }
+
+    /// Applies the settings that belong to a peripheral.  The rest are read
+    /// by whatever uses them.
+    pub fn apply(&self, board: &mut impl Board) {
+        board.display().set_contrast(self.contrast);
+        board.buzzer().set_volume(self.volume);
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    #[test]
+    fn test_set() {
+        let mut settings = Settings::DEFAULT;
+
+        assert!(settings.set(CONTRAST, 80));
+        assert!(!settings.set(CONTRAST, 101));
+        assert!(!settings.set(KEY_TONES, 2));
+        assert!(settings.set(KEY_TONES, 0));
+        assert!(!settings.set("brightness", 1));
+        assert!(settings.set(TIME_ZONE, 1));
+        assert!(!settings.set(TIME_ZONE, ZONES.len() as i32));
+
+        assert_eq!(settings.get(CONTRAST), Some(80));
+        assert_eq!(settings.get(KEY_TONES), Some(0));
+        assert_eq!(settings.get("brightness"), None);
+        assert_eq!(settings.time_zone(), ZONES[1].1);
+    }
+
+    #[test]
+    fn test_load() {
+        let mut board = fake::Board::default();
+
+        let settings = embassy_futures::block_on(async {
+            board.storage.put(VOLUME, &20_i32).await.unwrap();
+            board.storage.put(CLOCK_24H, &7_i32).await.unwrap();
+            Settings::load(&mut board.storage).await
+        });
+        settings.apply(&mut board);
+
+        assert_eq!(settings.volume, 20);
+        assert!(settings.clock_24h, "invalid values are ignored");
+        assert_eq!(board.buzzer.volume, 20);
+        assert_eq!(board.display.contrast, Settings::DEFAULT.contrast);
+    }
+}
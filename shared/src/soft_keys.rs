@@ -0,0 +1,160 @@
+//! Labels for Select and Cancel along the bottom of the screen, saying what
+//! each does on the screen being shown.
+
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_4X6},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+
+use super::{Key, KeyEvent};
+
+/// Height of the bar.
+pub const HEIGHT: u32 = 7;
+
+/// What Select and Cancel do on one screen, as a label to show and an
+/// action of the screen's choosing to resolve the key to.  A key without a
+/// label does nothing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SoftKeys<'a, A> {
+    select: Option<(&'a str, A)>,
+    cancel: Option<(&'a str, A)>,
+}
+
+impl<A> Default for SoftKeys<'_, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, A> SoftKeys<'a, A> {
+    pub const fn new() -> Self {
+        Self {
+            select: None,
+            cancel: None,
+        }
+    }
+
+    pub fn select(mut self, label: &'a str, action: A) -> Self {
+        self.select = Some((label, action));
+        self
+    }
+
+    pub fn cancel(mut self, label: &'a str, action: A) -> Self {
+        self.cancel = Some((label, action));
+        self
+    }
+
+    /// Whether neither key is labelled.
+    pub fn is_empty(&self) -> bool {
+        self.select.is_none() && self.cancel.is_none()
+    }
+
+    /// The action `event` triggers, if it is a press of a labelled key.
+    pub fn resolve(&self, event: KeyEvent) -> Option<A>
+    where
+        A: Copy,
+    {
+        let key = match event {
+            KeyEvent::Down(Key::Select) => &self.select,
+            KeyEvent::Down(Key::Cancel) => &self.cancel,
+            _ => return None,
+        };
+        key.map(|(_, action)| action)
+    }
+
+    /// Draws the bar across the bottom [`HEIGHT`] pixels of `draw_target`,
+    /// the Select label in the middle and the Cancel label on the right, as
+    /// the keys are laid out.
+    pub fn draw<D>(&self, draw_target: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let bounding_box = draw_target.bounding_box();
+        let bar = Rectangle::new(
+            bounding_box.top_left
+                + Point::new(0, bounding_box.size.height.saturating_sub(HEIGHT) as i32),
+            Size::new(
+                bounding_box.size.width,
+                HEIGHT.min(bounding_box.size.height),
+            ),
+        );
+        let _ = bar
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(draw_target);
+
+        let character_style = MonoTextStyle::new(&FONT_4X6, BinaryColor::Off);
+        let bottom = bar.top_left.y + bar.size.height as i32 - 1;
+        for (key, x, alignment) in [
+            (&self.select, bar.center().x, Alignment::Center),
+            (
+                &self.cancel,
+                bar.top_left.x + bar.size.width as i32 - 1,
+                Alignment::Right,
+            ),
+        ] {
+            if let Some((label, _)) = key {
+                let text_style = TextStyleBuilder::new()
+                    .alignment(alignment)
+                    .baseline(Baseline::Bottom)
+                    .build();
+                let _ = Text::with_text_style(
+                    label,
+                    Point::new(x, bottom),
+                    character_style,
+                    text_style,
+                )
+                .draw(draw_target);
+            }
+        }
+    }
+}
+
+/// The part of `bounding_box` above the bar.
+pub fn above(bounding_box: Rectangle) -> Rectangle {
+    Rectangle::new(
+        bounding_box.top_left,
+        bounding_box.size.saturating_sub(Size::new(0, HEIGHT)),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Action {
+        Save,
+        Back,
+    }
+
+    #[test]
+    fn test_soft_keys() {
+        let mut display = fake::Display::default();
+        let soft_keys = SoftKeys::new().select("Save", Action::Save);
+
+        assert_eq!(
+            soft_keys.resolve(KeyEvent::Down(Key::Select)),
+            Some(Action::Save)
+        );
+        assert_eq!(soft_keys.resolve(KeyEvent::Down(Key::Cancel)), None);
+        assert_eq!(soft_keys.resolve(KeyEvent::Up(Key::Select)), None);
+        let soft_keys = soft_keys.cancel("Back", Action::Back);
+        assert_eq!(
+            soft_keys.resolve(KeyEvent::Down(Key::Cancel)),
+            Some(Action::Back)
+        );
+
+        soft_keys.draw(&mut display);
+        let bottom = fake::HEIGHT as i32 - 1;
+        let row = |from: i32, to: i32| {
+            (from..to).any(|x| display.pixel(Point::new(x, bottom - 1)) == Some(BinaryColor::Off))
+        };
+        assert!(!row(0, 30), "nothing on the left");
+        assert!(row(30, 54), "Select label in the middle");
+        assert!(row(66, fake::WIDTH as i32), "Cancel label on the right");
+    }
+}
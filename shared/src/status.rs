@@ -0,0 +1,188 @@
+//! Strip across the top of the screen showing the time and system state,
+//! drawn by [`run_app`](crate::run_app) above the area apps draw in.
+
+use embedded_graphics::{
+    Pixel,
+    mono_font::{MonoTextStyle, ascii::FONT_4X6},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Line, PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+
+/// Height of the strip, including the line under it.
+pub const HEIGHT: u32 = 8;
+
+const USB_ICON: [u8; 6] = [0x10, 0x38, 0x92, 0xfe, 0x10, 0x38];
+const USB_WIDTH: i32 = 7;
+const LOCK_ICON: [u8; 6] = [0x70, 0x88, 0x88, 0xf8, 0xd8, 0xf8];
+const LOCK_WIDTH: i32 = 5;
+const BATTERY_WIDTH: i32 = 11;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Status {
+    /// Local time from the [`Rtc`](crate::Rtc), in seconds.
+    pub timestamp: i64,
+    /// As in the [`settings`](crate::settings).
+    pub clock_24h: bool,
+    pub usb_connected: bool,
+    pub locked: bool,
+    /// Charge in percent, if the target can tell.
+    pub battery: Option<u8>,
+}
+
+/// Draws a 1 bit per pixel icon, most significant bit leftmost.
+fn draw_icon<D>(rows: &[u8], top_left: Point, draw_target: &mut D)
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let pixels = rows.iter().enumerate().flat_map(|(y, row)| {
+        (0..8)
+            .filter(move |x| row & (0x80 >> x) != 0)
+            .map(move |x| Pixel(top_left + Point::new(x, y as i32), BinaryColor::Off))
+    });
+    let _ = draw_target.draw_iter(pixels);
+}
+
+fn draw_battery<D>(charge: u8, top_left: Point, draw_target: &mut D)
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let outline = PrimitiveStyle::with_stroke(BinaryColor::Off, 1);
+    let fill = PrimitiveStyle::with_fill(BinaryColor::Off);
+    let body = Rectangle::new(top_left, Size::new(BATTERY_WIDTH as u32 - 1, 6));
+    let _ = body.into_styled(outline).draw(draw_target);
+    let _ = Rectangle::new(top_left + Point::new(BATTERY_WIDTH - 1, 2), Size::new(1, 2))
+        .into_styled(fill)
+        .draw(draw_target);
+
+    let level = (u32::from(charge.min(100)) * (body.size.width - 2)).div_ceil(100);
+    let _ = Rectangle::new(top_left + Point::new(1, 1), Size::new(level, 4))
+        .into_styled(fill)
+        .draw(draw_target);
+}
+
+/// The time of day, eg. `13:05`, or `1:05p` on a 12 hour clock.
+fn time(status: &Status) -> heapless::String<6> {
+    let seconds = status.timestamp.rem_euclid(24 * 60 * 60);
+    let (hour, minute) = (seconds / 3600, seconds / 60 % 60);
+    let mut time = heapless::String::new();
+    let _ = if status.clock_24h {
+        core::fmt::write(&mut time, format_args!("{:02}:{:02}", hour, minute))
+    } else {
+        let meridiem = if hour < 12 { 'a' } else { 'p' };
+        let hour = (hour + 11) % 12 + 1;
+        core::fmt::write(
+            &mut time,
+            format_args!("{}:{:02}{}", hour, minute, meridiem),
+        )
+    };
+    time
+}
+
+/// Draws `status` across the top [`HEIGHT`] pixels of `draw_target`.
+pub fn draw<D>(status: &Status, draw_target: &mut D)
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let bounding_box = draw_target.bounding_box();
+    let top_left = bounding_box.top_left;
+    let width = bounding_box.size.width as i32;
+
+    let _ = Rectangle::new(top_left, Size::new(bounding_box.size.width, HEIGHT))
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(draw_target);
+    let _ = Line::new(
+        top_left + Point::new(0, HEIGHT as i32 - 1),
+        top_left + Point::new(width - 1, HEIGHT as i32 - 1),
+    )
+    .into_styled(PrimitiveStyle::with_stroke(BinaryColor::Off, 1))
+    .draw(draw_target);
+
+    let _ = Text::with_baseline(
+        &time(status),
+        top_left + Point::new(1, 0),
+        MonoTextStyle::new(&FONT_4X6, BinaryColor::Off),
+        Baseline::Top,
+    )
+    .draw(draw_target);
+
+    // indicators are packed in from the right
+    let mut x = width;
+    if let Some(charge) = status.battery {
+        x -= BATTERY_WIDTH;
+        draw_battery(charge, top_left + Point::new(x, 0), draw_target);
+        x -= 2;
+    }
+    if status.locked {
+        x -= LOCK_WIDTH;
+        draw_icon(&LOCK_ICON, top_left + Point::new(x, 0), draw_target);
+        x -= 2;
+    }
+    if status.usb_connected {
+        x -= USB_WIDTH;
+        draw_icon(&USB_ICON, top_left + Point::new(x, 0), draw_target);
+    }
+}
+
+/// The part of `bounding_box` below the strip.
+pub fn below(bounding_box: Rectangle) -> Rectangle {
+    Rectangle::new(
+        bounding_box.top_left + Point::new(0, HEIGHT as i32),
+        bounding_box.size.saturating_sub(Size::new(0, HEIGHT)),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    #[test]
+    fn test_draw() {
+        let mut display = fake::Display::default();
+        let status = Status {
+            timestamp: 12 * 3600 + 34 * 60,
+            clock_24h: true,
+            usb_connected: false,
+            locked: true,
+            battery: Some(100),
+        };
+
+        draw(&status, &mut display);
+
+        let right = fake::WIDTH as i32 - 1;
+        // battery nub, then the top of the lock's shackle left of the battery
+        assert_eq!(display.pixel(Point::new(right, 2)), Some(BinaryColor::Off));
+        assert_eq!(
+            display.pixel(Point::new(right - BATTERY_WIDTH - 2 - 3, 0)),
+            Some(BinaryColor::Off)
+        );
+        // no USB icon left of the lock
+        assert_eq!(
+            display.pixel(Point::new(right - BATTERY_WIDTH - 2 - LOCK_WIDTH - 4, 3)),
+            Some(BinaryColor::On)
+        );
+        assert_eq!(
+            display.pixel(Point::new(40, HEIGHT as i32 - 1)),
+            Some(BinaryColor::Off)
+        );
+    }
+
+    #[test]
+    fn test_time() {
+        let mut status = Status {
+            timestamp: 13 * 3600 + 5 * 60,
+            clock_24h: true,
+            usb_connected: false,
+            locked: false,
+            battery: None,
+        };
+        assert_eq!(time(&status), "13:05");
+
+        status.clock_24h = false;
+        assert_eq!(time(&status), "1:05p");
+        status.timestamp = 30 * 60;
+        assert_eq!(time(&status), "12:30a");
+    }
+}
@@ -0,0 +1,177 @@
+//! Small records kept across reboots, looked up by name.
+//!
+//! Backends only store bytes: [`Storage::get`] and [`Storage::put`] turn
+//! them into and out of any [`Record`].
+
+use core::future::Future;
+
+/// Longest key, in bytes.
+pub const MAX_KEY: usize = 16;
+/// Largest value, in bytes.
+pub const MAX_VALUE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The key is empty or longer than [`MAX_KEY`].
+    InvalidKey,
+    /// The value is larger than [`MAX_VALUE`], or than the buffer it was
+    /// read into.
+    TooLarge,
+    /// There is no room left for the value.
+    Full,
+    /// What was stored could not be read back as the type asked for.
+    Corrupt,
+    /// The underlying flash or browser storage failed.
+    Backend,
+}
+
+/// Checks that `key` can be stored.
+pub fn check_key(key: &str) -> Result<(), Error> {
+    if key.is_empty() || key.len() > MAX_KEY {
+        Err(Error::InvalidKey)
+    } else {
+        Ok(())
+    }
+}
+
+/// A value that can be stored, in at most [`MAX_VALUE`] bytes.
+pub trait Record: Sized {
+    /// Writes `self` to the start of `buffer`, returning how many bytes it
+    /// took, or `None` if it does not fit.
+    fn encode(&self, buffer: &mut [u8]) -> Option<usize>;
+
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! integer_record {
+    ($($t:ty),*) => {$(
+        impl Record for $t {
+            fn encode(&self, buffer: &mut [u8]) -> Option<usize> {
+                let bytes = self.to_le_bytes();
+                buffer.get_mut(..bytes.len())?.copy_from_slice(&bytes);
+                Some(bytes.len())
+            }
+
+            fn decode(bytes: &[u8]) -> Option<Self> {
+                Some(Self::from_le_bytes(bytes.try_into().ok()?))
+            }
+        }
+    )*};
+}
+
+integer_record!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl Record for bool {
+    fn encode(&self, buffer: &mut [u8]) -> Option<usize> {
+        u8::from(*self).encode(buffer)
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        match u8::decode(bytes)? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl<const N: usize> Record for heapless::Vec<u8, N> {
+    fn encode(&self, buffer: &mut [u8]) -> Option<usize> {
+        buffer.get_mut(..self.len())?.copy_from_slice(self);
+        Some(self.len())
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        heapless::Vec::from_slice(bytes).ok()
+    }
+}
+
+impl<const N: usize> Record for heapless::String<N> {
+    fn encode(&self, buffer: &mut [u8]) -> Option<usize> {
+        buffer
+            .get_mut(..self.len())?
+            .copy_from_slice(self.as_bytes());
+        Some(self.len())
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut string = heapless::String::new();
+        string.push_str(core::str::from_utf8(bytes).ok()?).ok()?;
+        Some(string)
+    }
+}
+
+/// Somewhere to keep records that outlives the app, and usually the power.
+pub trait Storage {
+    /// Copies the value stored under `key` into `buffer`, returning its
+    /// length, or `None` if nothing is stored under `key`.
+    fn read(
+        &mut self,
+        key: &str,
+        buffer: &mut [u8],
+    ) -> impl Future<Output = Result<Option<usize>, Error>>;
+
+    /// Stores `value` under `key`, replacing whatever was there.  Once this
+    /// resolves the value survives losing power.
+    fn write(&mut self, key: &str, value: &[u8]) -> impl Future<Output = Result<(), Error>>;
+
+    /// Removes whatever is stored under `key`, if anything.
+    fn delete(&mut self, key: &str) -> impl Future<Output = Result<(), Error>>;
+
+    fn get<R: Record>(&mut self, key: &str) -> impl Future<Output = Result<Option<R>, Error>> {
+        async move {
+            let mut buffer = [0; MAX_VALUE];
+            match self.read(key, &mut buffer).await? {
+                Some(length) => R::decode(&buffer[..length]).map(Some).ok_or(Error::Corrupt),
+                None => Ok(None),
+            }
+        }
+    }
+
+    fn put<R: Record>(&mut self, key: &str, value: &R) -> impl Future<Output = Result<(), Error>> {
+        let mut buffer = [0; MAX_VALUE];
+        let length = value.encode(&mut buffer);
+        async move {
+            let length = length.ok_or(Error::TooLarge)?;
+            self.write(key, &buffer[..length]).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    #[test]
+    fn test_records() {
+        let mut storage = fake::Storage::default();
+        let name: heapless::String<8> = heapless::String::try_from("Nokia").unwrap();
+
+        embassy_futures::block_on(async {
+            storage.put("score", &1234_u32).await.unwrap();
+            storage.put("name", &name).await.unwrap();
+            storage.put("sound", &true).await.unwrap();
+
+            assert_eq!(storage.get::<u32>("score").await, Ok(Some(1234)));
+            assert_eq!(
+                storage.get::<heapless::String<8>>("name").await,
+                Ok(Some(name))
+            );
+            assert_eq!(storage.get::<bool>("sound").await, Ok(Some(true)));
+            assert_eq!(storage.get::<u16>("score").await, Err(Error::Corrupt));
+
+            storage.put("score", &99_u32).await.unwrap();
+            assert_eq!(storage.get::<u32>("score").await, Ok(Some(99)));
+            storage.delete("score").await.unwrap();
+            assert_eq!(storage.get::<u32>("score").await, Ok(None));
+
+            assert_eq!(
+                storage.put("a key that is far too long", &0_u8).await,
+                Err(Error::InvalidKey)
+            );
+            let large: heapless::Vec<u8, 128> = heapless::Vec::from_slice(&[0; 100]).unwrap();
+            assert_eq!(storage.put("large", &large).await, Err(Error::TooLarge));
+        });
+    }
+}
@@ -0,0 +1,324 @@
+//! Fulfilling [`SystemRequest`]s on behalf of applications.
+
+use core::future::Future;
+
+use embassy_futures::select::{Either3, select, select3};
+use embassy_time::{Duration, with_timeout};
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PrimitiveStyle, PrimitiveStyleBuilder, StrokeAlignment},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+
+use crate::{
+    Board, KeyEvent, Keypad, RequestError, Rtc, RtcError, SystemRequest, SystemResponse, UsbRx,
+    UsbTx, Volume, audio,
+    backlight::{Timeout, Waking},
+    keyguard::Keyguard,
+    keypad::{Clicks, KeyTones},
+    protocol::Message,
+    settings::Settings,
+    storage::Storage,
+    text_input::text_input,
+};
+
+/// How long a notification stays up if no key is pressed.
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The parts of the system that differ between targets.
+pub trait System {
+    /// Sends `tx` to the host.
+    fn usb_tx(&mut self, tx: UsbTx) -> impl Future<Output = Result<(), RequestError>>;
+
+    /// Lock state of the keypad, kept for as long as the system runs.
+    fn keyguard(&self) -> &Keyguard;
+
+    /// When the backlight goes off, kept for as long as the system runs.
+    fn backlight(&self) -> &Timeout;
+
+    /// Preferences in effect, as restored by the
+    /// [`Launcher`](crate::registry::Launcher) on boot.
+    fn settings(&mut self) -> &mut Settings;
+
+    /// Whether a host is connected over USB, for the [`status`](crate::status)
+    /// strip.
+    fn usb_connected(&self) -> bool {
+        false
+    }
+
+    /// Battery charge in percent, if the target can measure it.
+    fn battery(&mut self) -> Option<u8> {
+        None
+    }
+
+    /// Takes the next message received from the host, if there is one.
+    fn usb_rx(&mut self) -> Option<UsbRx>;
+
+    /// Takes the next message from the host that is meant for the running
+    /// app, answering any meant for the system along the way.  Targets that
+    /// [`answer`] the host from a task of their own only ever queue messages
+    /// for apps here.
+    fn receive(&mut self, board: &mut impl Board) -> impl Future<Output = Option<UsbRx>> {
+        async move {
+            loop {
+                let message = self.usb_rx()?;
+                let Some(reply) = answer(&message, board.rtc()) else {
+                    return Some(message);
+                };
+                if let Err(e) = self.usb_tx(UsbTx::Cdc(reply)).await {
+                    log::warn!("could not reply to host: {:?}", e);
+                }
+            }
+        }
+    }
+
+    /// Carries out `request` and returns the response for the app's next
+    /// `run`.  [`SystemRequest::Launch`] is handled by
+    /// [`run_app`](crate::run_app) and never reaches here.
+    fn fulfil(
+        &mut self,
+        request: SystemRequest,
+        board: &mut impl Board,
+    ) -> impl Future<Output = SystemResponse> {
+        async move {
+            match request {
+                SystemRequest::TextInput { prompt } => {
+                    let key_tones = self.settings().key_clicks();
+                    let peripherals = board.peripherals();
+                    let clicks = Clicks::new();
+                    let mut keypad = Waking::new(
+                        KeyTones::new(peripherals.keypad, &clicks, key_tones),
+                        self.backlight(),
+                    );
+                    match select3(
+                        text_input(prompt, &mut keypad, peripherals.display),
+                        clicks.play(peripherals.buzzer),
+                        self.backlight().drive(peripherals.backlight),
+                    )
+                    .await
+                    {
+                        Either3::First(Some(text)) => SystemResponse::Text(text),
+                        Either3::First(None) => SystemResponse::Cancelled,
+                        Either3::Second(never) | Either3::Third(never) => match never {},
+                    }
+                }
+                SystemRequest::Notify(message) => {
+                    notify(&message, board, self.backlight()).await;
+                    SystemResponse::Done
+                }
+                SystemRequest::Usb(tx) => match self.usb_tx(tx).await {
+                    Ok(()) => SystemResponse::Done,
+                    Err(e) => SystemResponse::Failed(e),
+                },
+                SystemRequest::Launch(_) => SystemResponse::Done,
+                SystemRequest::SaveSetting { key, value } => {
+                    if !self.settings().set(key, value) {
+                        return SystemResponse::Failed(RequestError::Unsupported);
+                    }
+                    self.settings().apply(board);
+                    let seconds = self.settings().backlight_timeout;
+                    self.backlight().set_timeout(seconds);
+                    match board.storage().put(key, &value).await {
+                        Ok(()) => SystemResponse::Done,
+                        Err(e) => {
+                            log::warn!("could not save {}: {:?}", key, e);
+                            SystemResponse::Failed(RequestError::Unavailable)
+                        }
+                    }
+                }
+                SystemRequest::PlayRingtone(text) => {
+                    let settings = *self.settings();
+                    let peripherals = board.peripherals();
+                    let mut buzzer = Volume::new(peripherals.buzzer, &settings);
+                    let mut keypad = Waking::new(peripherals.keypad, self.backlight());
+                    let song = rtttl::Song::new(text);
+                    // any key press stops it, but not the release of the one
+                    // that started it
+                    select3(
+                        audio::play(&mut buzzer, &song),
+                        async { while !matches!(keypad.event().await, KeyEvent::Down(_)) {} },
+                        self.backlight().drive(peripherals.backlight),
+                    )
+                    .await;
+                    SystemResponse::Done
+                }
+                SystemRequest::SetTime(local) => {
+                    let timestamp = self.settings().time_zone().to_utc(local);
+                    match board.rtc().set_timestamp(timestamp) {
+                        Ok(()) => SystemResponse::Done,
+                        Err(e) => {
+                            log::warn!("could not set the time: {:?}", e);
+                            SystemResponse::Failed(match e {
+                                RtcError::OutOfRange => RequestError::Unsupported,
+                                RtcError::Bus => RequestError::Unavailable,
+                            })
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl SystemRequest {
+    /// Whether fulfilling the request takes over the screen and keypad.
+    pub fn is_modal(&self) -> bool {
+        matches!(
+            self,
+            SystemRequest::TextInput { .. }
+                | SystemRequest::Notify(_)
+                | SystemRequest::PlayRingtone(_)
+        )
+    }
+}
+
+/// The reply to `message` if it is meant for the system rather than an app,
+/// or `None` if it is for the app.
+pub fn answer(message: &Message, rtc: &mut impl Rtc) -> Option<Message> {
+    Some(match message {
+        Message::Ping => Message::Pong,
+        Message::GetTime => Message::Time(rtc.timestamp()),
+        Message::SetTime(timestamp) => match rtc.set_timestamp(*timestamp) {
+            Ok(()) => Message::Ack,
+            Err(e) => {
+                log::warn!("host could not set the time: {:?}", e);
+                Message::Nack
+            }
+        },
+        _ => return None,
+    })
+}
+
+/// Shows `message` in a box until a key is pressed or
+/// [`NOTIFICATION_TIMEOUT`] passes, with the backlight following `timeout`.
+pub async fn notify(message: &str, board: &mut impl Board, timeout: &Timeout) {
+    let peripherals = board.peripherals();
+    draw_notification(message, peripherals.display);
+
+    let _ = with_timeout(
+        NOTIFICATION_TIMEOUT,
+        select(
+            async { while !matches!(peripherals.keypad.event().await, KeyEvent::Down(_)) {} },
+            timeout.drive(peripherals.backlight),
+        ),
+    )
+    .await;
+
+    clear_notification(peripherals.display);
+}
+
+pub(crate) fn draw_notification<D>(message: &str, display: &mut D)
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let bounding_box = display.bounding_box();
+    let border = PrimitiveStyleBuilder::new()
+        .stroke_color(BinaryColor::Off)
+        .stroke_width(2)
+        .stroke_alignment(StrokeAlignment::Inside)
+        .fill_color(BinaryColor::On)
+        .build();
+    let _ = bounding_box.into_styled(border).draw(display);
+    let _ = Text::with_text_style(
+        message,
+        bounding_box.center(),
+        MonoTextStyle::new(&FONT_6X10, BinaryColor::Off),
+        TextStyleBuilder::new()
+            .alignment(Alignment::Center)
+            .baseline(Baseline::Middle)
+            .build(),
+    )
+    .draw(display);
+}
+
+pub(crate) fn clear_notification<D>(display: &mut D)
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let _ = display
+        .bounding_box()
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(display);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Key, fake, settings};
+
+    #[test]
+    fn test_answer() {
+        let mut rtc = fake::Rtc(0);
+
+        assert_eq!(answer(&Message::SetTime(42), &mut rtc), Some(Message::Ack));
+        assert_eq!(answer(&Message::GetTime, &mut rtc), Some(Message::Time(42)));
+        assert_eq!(answer(&Message::Data(heapless::Vec::new()), &mut rtc), None);
+    }
+
+    #[test]
+    fn test_ringtone_outlasts_release() {
+        let mut board = fake::Board::default();
+        let mut system = fake::System::default();
+        board.keypad.push(KeyEvent::Up(Key::Select));
+        // four sixteenths at 600 bpm, 25ms each
+        let song = "Test:d=16,o=5,b=600:a,p,c6,e";
+
+        let started = embassy_time::Instant::now();
+        let response =
+            embassy_futures::block_on(system.fulfil(SystemRequest::PlayRingtone(song), &mut board));
+
+        assert!(matches!(response, SystemResponse::Done));
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert_eq!(board.buzzer.frequency, 659, "played to the last note");
+        assert!(board.backlight.on, "the release should light the backlight");
+    }
+
+    #[test]
+    fn test_fulfil() {
+        let mut board = fake::Board::default();
+        let mut system = fake::System::default();
+        board.keypad.push(KeyEvent::Down(Key::Two));
+        board.keypad.push(KeyEvent::Down(Key::Select));
+
+        embassy_futures::block_on(async {
+            assert!(matches!(
+                system
+                    .fulfil(SystemRequest::Usb(UsbTx::HidChar('a')), &mut board)
+                    .await,
+                SystemResponse::Done
+            ));
+            assert!(matches!(
+                system
+                    .fulfil(SystemRequest::TextInput { prompt: "Name:" }, &mut board)
+                    .await,
+                SystemResponse::Text(text) if text == "A"
+            ));
+            assert!(matches!(
+                system
+                    .fulfil(SystemRequest::SetTime(1_700_000_000), &mut board)
+                    .await,
+                SystemResponse::Done
+            ));
+
+            let save = SystemRequest::SaveSetting {
+                key: settings::CONTRAST,
+                value: 70,
+            };
+            assert!(matches!(
+                system.fulfil(save, &mut board).await,
+                SystemResponse::Done
+            ));
+            assert_eq!(
+                board.storage.get::<i32>(settings::CONTRAST).await,
+                Ok(Some(70))
+            );
+        });
+        assert_eq!(board.rtc.0, 1_700_000_000);
+        assert_eq!(system.settings.contrast, 70);
+        assert_eq!(board.display.contrast, 70);
+
+        assert!(matches!(system.sent[..], [UsbTx::HidChar('a')]));
+    }
+}
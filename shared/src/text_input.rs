@@ -0,0 +1,151 @@
+use embassy_time::{Duration, with_timeout};
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+use multi_tap::{Input, Letters};
+
+use super::{Key, KeyEvent, Keypad};
+
+/// How long to wait for another tap on the same key before its letter is
+/// decided.
+pub(crate) const TAP_TIMEOUT: Duration = Duration::from_millis(1000);
+
+impl Letters for Key {
+    fn letters(self) -> &'static [u8] {
+        match self {
+            Key::One => b".,?!1",
+            Key::Two => b"ABC2",
+            Key::Three => b"DEF3",
+            Key::Four => b"GHI4",
+            Key::Five => b"JKL5",
+            Key::Six => b"MNO6",
+            Key::Seven => b"PQRS7",
+            Key::Eight => b"TUV8",
+            Key::Nine => b"WXYZ9",
+            Key::Zero => b" 0",
+            _ => b"",
+        }
+    }
+}
+
+fn draw<D, const N: usize>(prompt: &str, input: &Input<Key, N>, draw_target: &mut D)
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let bounding_box = draw_target.bounding_box();
+    let top_left = bounding_box.top_left;
+    let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+
+    let _ = bounding_box
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(draw_target);
+    let _ = Text::with_baseline(prompt, top_left + Point::new(2, 1), style, Baseline::Top)
+        .draw(draw_target);
+
+    // only the end of the text is shown once it is wider than the screen
+    let columns = (bounding_box.size.width as usize - 4) / 6;
+    let text = input.text();
+    let shown = &text[text.len().saturating_sub(columns.saturating_sub(1))..];
+    let next = Text::with_baseline(shown, top_left + Point::new(2, 14), style, Baseline::Top)
+        .draw(draw_target)
+        .unwrap_or(top_left);
+
+    let cursor = Rectangle::new(next, Size::new(6, 10));
+    let _ = cursor
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+        .draw(draw_target);
+    if let Some(c) = input.tentative() {
+        let mut buffer = [0; 4];
+        let _ = Text::with_baseline(
+            c.encode_utf8(&mut buffer),
+            next,
+            MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+            Baseline::Top,
+        )
+        .draw(draw_target);
+    }
+}
+
+/// Modal text entry.  Select accepts the text, Cancel deletes a letter or,
+/// when there is nothing left to delete, abandons entry and returns `None`.
+/// Holding a digit types the digit and holding Cancel clears the text.
+pub async fn text_input<KEYPAD, D, const N: usize>(
+    prompt: &str,
+    keypad: &mut KEYPAD,
+    draw_target: &mut D,
+) -> Option<heapless::String<N>>
+where
+    KEYPAD: Keypad,
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let mut input = Input::<Key, N>::new();
+
+    loop {
+        draw(prompt, &input, draw_target);
+
+        let event = if input.tentative().is_some() {
+            match with_timeout(TAP_TIMEOUT, keypad.event()).await {
+                Ok(event) => event,
+                Err(_) => {
+                    input.decide();
+                    continue;
+                }
+            }
+        } else {
+            keypad.event().await
+        };
+
+        match event {
+            KeyEvent::Down(Key::Select) => return Some(input.into_text()),
+            KeyEvent::Down(Key::Cancel) => {
+                if !input.backspace() {
+                    return None;
+                }
+            }
+            KeyEvent::Down(key) => {
+                input.press(key);
+            }
+            KeyEvent::LongPress(Key::Cancel) => input.clear(),
+            KeyEvent::LongPress(key) => input.hold(key),
+            KeyEvent::Up(_) | KeyEvent::Repeat(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    #[test]
+    fn test_text_input() {
+        let mut keypad = fake::Keypad::default();
+        let mut display = fake::Display::default();
+        for key in [Key::Four, Key::Four, Key::Four, Key::Zero, Key::Select] {
+            keypad.push(KeyEvent::Down(key));
+        }
+
+        let text =
+            embassy_futures::block_on(text_input::<_, _, 8>("Name:", &mut keypad, &mut display));
+
+        assert_eq!(text.as_deref(), Some("I "));
+    }
+
+    #[test]
+    fn test_text_input_cancelled() {
+        let mut keypad = fake::Keypad::default();
+        let mut display = fake::Display::default();
+        for key in [Key::Two, Key::Cancel, Key::Cancel] {
+            keypad.push(KeyEvent::Down(key));
+        }
+
+        let text =
+            embassy_futures::block_on(text_input::<_, _, 8>("Name:", &mut keypad, &mut display));
+
+        assert!(text.is_none());
+    }
+}
@@ -0,0 +1,255 @@
+//! Local time, from the UTC kept by the [`Rtc`].
+//!
+//! Zones are picked from [`ZONES`] in the [`settings`](crate::settings), and
+//! each is either a fixed offset or a rule for when daylight saving starts
+//! and ends, in the style of a POSIX `TZ` string.  Apps read and set local
+//! time through [`Local`] without converting themselves.
+
+use crate::{
+    Rtc, RtcError,
+    calendar::{SECONDS_PER_DAY, civil_from_days, days_from_civil, days_in_month},
+};
+
+/// When daylight saving starts or ends, eg. the last Sunday of March at 1am.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub month: u8,
+    /// 1 to 4 for that week of the month, 5 for the last.
+    pub week: u8,
+    /// 0 for Sunday.
+    pub weekday: u8,
+    /// Minutes after midnight, in the local time in effect until the
+    /// transition.
+    pub minute: u16,
+}
+
+impl Transition {
+    /// Seconds since 1970-01-01 in local time at which this transition falls
+    /// in `year`.
+    fn local(&self, year: i32) -> i64 {
+        let month = u32::from(self.month);
+        let first = days_from_civil(year, month, 1);
+        // 1970-01-01 was a Thursday
+        let first_weekday = (first + 4).rem_euclid(7);
+        let mut day = (i64::from(self.weekday) - first_weekday).rem_euclid(7)
+            + 7 * i64::from(self.week.max(1) - 1);
+        while day >= i64::from(days_in_month(year, month)) {
+            day -= 7;
+        }
+        (first + day) * SECONDS_PER_DAY + i64::from(self.minute) * 60
+    }
+}
+
+/// Daylight saving, in a zone `standard` minutes east of UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rule {
+    pub standard: i16,
+    /// Minutes the clocks go forward by.
+    pub saving: i16,
+    pub start: Transition,
+    pub end: Transition,
+}
+
+impl Rule {
+    /// Whether daylight saving is in effect at `utc`.
+    fn in_effect(&self, utc: i64) -> bool {
+        let standard = i64::from(self.standard) * 60;
+        let daylight = standard + i64::from(self.saving) * 60;
+        let (year, ..) = civil_from_days((utc + standard).div_euclid(SECONDS_PER_DAY));
+        let start = self.start.local(year) - standard;
+        let end = self.end.local(year) - daylight;
+        if start < end {
+            start <= utc && utc < end
+        } else {
+            // southern hemisphere, with daylight saving over the new year
+            utc < end || start <= utc
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeZone {
+    /// Minutes east of UTC, all year round.
+    Fixed(i16),
+    Rule(Rule),
+}
+
+impl TimeZone {
+    pub const UTC: Self = Self::Fixed(0);
+
+    /// Seconds to add to UTC to get local time at `utc`.
+    pub fn offset(&self, utc: i64) -> i64 {
+        match self {
+            Self::Fixed(minutes) => i64::from(*minutes) * 60,
+            Self::Rule(rule) if rule.in_effect(utc) => {
+                (i64::from(rule.standard) + i64::from(rule.saving)) * 60
+            }
+            Self::Rule(rule) => i64::from(rule.standard) * 60,
+        }
+    }
+
+    pub fn to_local(&self, utc: i64) -> i64 {
+        utc + self.offset(utc)
+    }
+
+    /// The inverse of [`to_local`](Self::to_local).  A time that happens
+    /// twice as the clocks go back is taken as the first, and one skipped as
+    /// they go forward as if they had not yet.
+    pub fn to_utc(&self, local: i64) -> i64 {
+        let (standard, daylight) = match self {
+            Self::Fixed(minutes) => (i64::from(*minutes) * 60, i64::from(*minutes) * 60),
+            Self::Rule(rule) => (
+                i64::from(rule.standard) * 60,
+                (i64::from(rule.standard) + i64::from(rule.saving)) * 60,
+            ),
+        };
+        if self.offset(local - daylight) == daylight {
+            local - daylight
+        } else {
+            local - standard
+        }
+    }
+}
+
+const fn at(month: u8, week: u8, hour: u16) -> Transition {
+    Transition {
+        month,
+        week,
+        weekday: 0,
+        minute: hour * 60,
+    }
+}
+
+const fn rule(standard: i16, start: Transition, end: Transition) -> TimeZone {
+    TimeZone::Rule(Rule {
+        standard,
+        saving: 60,
+        start,
+        end,
+    })
+}
+
+/// Zones to pick from, by name.  The [`settings`](crate::settings) store the
+/// index of one.
+pub const ZONES: &[(&str, TimeZone)] = &[
+    ("UTC", TimeZone::UTC),
+    ("London", rule(0, at(3, 5, 1), at(10, 5, 2))),
+    ("Paris", rule(60, at(3, 5, 2), at(10, 5, 3))),
+    ("Helsinki", rule(120, at(3, 5, 3), at(10, 5, 4))),
+    ("Moscow", TimeZone::Fixed(180)),
+    ("Dubai", TimeZone::Fixed(240)),
+    ("India", TimeZone::Fixed(330)),
+    ("China", TimeZone::Fixed(480)),
+    ("Tokyo", TimeZone::Fixed(540)),
+    ("Sydney", rule(600, at(10, 1, 2), at(4, 1, 3))),
+    ("Auckland", rule(720, at(9, 5, 2), at(4, 1, 3))),
+    ("Sao Paulo", TimeZone::Fixed(-180)),
+    ("New York", rule(-300, at(3, 2, 2), at(11, 1, 2))),
+    ("Chicago", rule(-360, at(3, 2, 2), at(11, 1, 2))),
+    ("Denver", rule(-420, at(3, 2, 2), at(11, 1, 2))),
+    ("Phoenix", TimeZone::Fixed(-420)),
+    ("Los Angeles", rule(-480, at(3, 2, 2), at(11, 1, 2))),
+    ("Honolulu", TimeZone::Fixed(-600)),
+];
+
+/// An RTC read and set in local time, as apps are given.
+pub struct Local<'a, R> {
+    rtc: &'a mut R,
+    time_zone: TimeZone,
+}
+
+impl<'a, R> Local<'a, R> {
+    pub fn new(rtc: &'a mut R, time_zone: TimeZone) -> Self {
+        Self { rtc, time_zone }
+    }
+}
+
+impl<R: Rtc> Rtc for Local<'_, R> {
+    fn timestamp(&mut self) -> i64 {
+        self.time_zone.to_local(self.rtc.timestamp())
+    }
+
+    fn set_timestamp(&mut self, timestamp: i64) -> Result<(), RtcError> {
+        self.rtc.set_timestamp(self.time_zone.to_utc(timestamp))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    fn zone(name: &str) -> TimeZone {
+        ZONES.iter().find(|(n, _)| *n == name).unwrap().1
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: i64, minute: i64) -> i64 {
+        days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60
+    }
+
+    #[test]
+    fn test_fixed() {
+        let india = zone("India");
+        let noon = utc(2024, 6, 1, 12, 0);
+
+        assert_eq!(india.to_local(noon), utc(2024, 6, 1, 17, 30));
+        assert_eq!(india.to_utc(india.to_local(noon)), noon);
+        assert_eq!(TimeZone::UTC.to_local(noon), noon);
+    }
+
+    #[test]
+    fn test_transitions() {
+        let hour = 3600;
+        // (zone, when the clocks change, offset before, offset after)
+        let cases = [
+            ("London", utc(2024, 3, 31, 1, 0), 0, hour),
+            ("London", utc(2024, 10, 27, 1, 0), hour, 0),
+            ("Paris", utc(2024, 3, 31, 1, 0), hour, 2 * hour),
+            ("Paris", utc(2024, 10, 27, 1, 0), 2 * hour, hour),
+            ("New York", utc(2024, 3, 10, 7, 0), -5 * hour, -4 * hour),
+            ("New York", utc(2024, 11, 3, 6, 0), -4 * hour, -5 * hour),
+            ("Sydney", utc(2024, 4, 6, 16, 0), 11 * hour, 10 * hour),
+            ("Sydney", utc(2024, 10, 5, 16, 0), 10 * hour, 11 * hour),
+            ("Auckland", utc(2024, 9, 28, 14, 0), 12 * hour, 13 * hour),
+        ];
+
+        for (name, change, before, after) in cases {
+            let zone = zone(name);
+            assert_eq!(
+                zone.offset(change - 1),
+                before,
+                "{} before {}",
+                name,
+                change
+            );
+            assert_eq!(zone.offset(change), after, "{} at {}", name, change);
+        }
+    }
+
+    #[test]
+    fn test_to_utc() {
+        let new_york = zone("New York");
+
+        // 1:30am happens twice as the clocks go back, first in daylight time
+        let twice = utc(2024, 11, 3, 1, 30);
+        assert_eq!(new_york.to_utc(twice), utc(2024, 11, 3, 5, 30));
+        // 2:30am never happens as they go forward, and reads as 3:30am
+        let skipped = utc(2024, 3, 10, 2, 30);
+        assert_eq!(new_york.to_utc(skipped), utc(2024, 3, 10, 7, 30));
+
+        for utc in (utc(2024, 1, 1, 0, 0)..utc(2025, 1, 1, 0, 0)).step_by(1800) {
+            let local = new_york.to_local(utc);
+            assert_eq!(new_york.to_local(new_york.to_utc(local)), local);
+        }
+    }
+
+    #[test]
+    fn test_local() {
+        let mut rtc = fake::Rtc(utc(2024, 7, 1, 12, 0));
+        let mut local = Local::new(&mut rtc, zone("London"));
+
+        assert_eq!(local.timestamp(), utc(2024, 7, 1, 13, 0));
+        local.set_timestamp(utc(2024, 7, 1, 9, 0)).unwrap();
+        assert_eq!(rtc.0, utc(2024, 7, 1, 8, 0));
+    }
+}
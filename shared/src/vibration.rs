@@ -0,0 +1,138 @@
+//! Timed vibration patterns, so that apps need not time the motor by hand.
+//!
+//! ```ignore
+//! vibration::play(&vibration::NOTIFICATION, board.vibration_motor()).await;
+//! ```
+
+use embassy_time::Timer;
+
+use crate::VibrationMotor;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Repeat {
+    Times(u16),
+    /// Until the future playing it is dropped.
+    Forever,
+}
+
+/// Steps of (on, off) in milliseconds, played through `repeat` times.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pattern<'a> {
+    pub steps: &'a [(u32, u32)],
+    pub repeat: Repeat,
+}
+
+/// An incoming call, until it is answered.
+pub const RING: Pattern<'static> = Pattern {
+    steps: &[(1000, 1500)],
+    repeat: Repeat::Forever,
+};
+
+/// A short double buzz.
+pub const NOTIFICATION: Pattern<'static> = Pattern {
+    steps: &[(150, 100), (150, 0)],
+    repeat: Repeat::Times(1),
+};
+
+/// Bursts of three, until dismissed.
+pub const ALARM: Pattern<'static> = Pattern {
+    steps: &[(300, 150), (300, 150), (300, 1000)],
+    repeat: Repeat::Forever,
+};
+
+/// Stops the motor when dropped, so that a cancelled pattern never leaves it
+/// running.
+struct Running<'a, M: VibrationMotor>(&'a mut M);
+
+impl<M: VibrationMotor> Drop for Running<'_, M> {
+    fn drop(&mut self) {
+        self.0.stop();
+    }
+}
+
+/// Plays `pattern` on `motor`.  Dropping the future part way through, eg.
+/// when `run` is pre-empted, stops the motor.  A pattern without steps
+/// finishes at once, even if it repeats forever.
+pub async fn play(pattern: &Pattern<'_>, motor: &mut impl VibrationMotor) {
+    if pattern.steps.is_empty() {
+        return;
+    }
+    let motor = Running(motor);
+    let mut played = 0;
+    loop {
+        if let Repeat::Times(times) = pattern.repeat {
+            if played == times {
+                return;
+            }
+            played += 1;
+        }
+        for &(on, off) in pattern.steps {
+            motor.0.start();
+            Timer::after_millis(on.into()).await;
+            motor.0.stop();
+            Timer::after_millis(off.into()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use embassy_time::{Duration, Instant, with_timeout};
+
+    use super::*;
+    use crate::fake;
+
+    #[test]
+    fn test_play() {
+        let mut motor = fake::VibrationMotor::default();
+        let pattern = Pattern {
+            steps: &[(20, 10), (30, 0)],
+            repeat: Repeat::Times(2),
+        };
+
+        let started = Instant::now();
+        embassy_futures::block_on(play(&pattern, &mut motor));
+
+        let starts: heapless::Vec<_, 8> = motor
+            .history
+            .iter()
+            .filter(|(running, _)| *running)
+            .map(|(_, at)| *at - started)
+            .collect();
+        assert_eq!(starts.len(), 4);
+        assert!(starts[1] >= Duration::from_millis(30));
+        assert!(starts[2] >= Duration::from_millis(60));
+        assert!(!motor.running);
+    }
+
+    #[test]
+    fn test_play_empty() {
+        let mut motor = fake::VibrationMotor::default();
+        let pattern = Pattern {
+            steps: &[],
+            repeat: Repeat::Forever,
+        };
+
+        let result = embassy_futures::block_on(with_timeout(
+            Duration::from_millis(50),
+            play(&pattern, &mut motor),
+        ));
+
+        assert!(result.is_ok());
+        assert!(motor.history.is_empty());
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut motor = fake::VibrationMotor::default();
+
+        let result = embassy_futures::block_on(with_timeout(
+            Duration::from_millis(50),
+            play(&RING, &mut motor),
+        ));
+
+        assert!(result.is_err());
+        assert_eq!(motor.history.len(), 2, "started once, then stopped");
+        assert!(!motor.running, "dropping the pattern should stop the motor");
+    }
+}
@@ -0,0 +1,537 @@
+//! Modal dialogs for apps to ask the user something.  Like
+//! [`Menu::process`](crate::menu::Menu::process) they read from a keypad and
+//! draw to whatever target they are given, and like
+//! [`text_input`](crate::text_input::text_input) they only return once the
+//! user has answered: with the answer on Select, or `None` on Cancel, as
+//! labelled by their [`SoftKeys`].
+
+use core::{fmt::Write, ops::RangeInclusive};
+
+use embedded_graphics::{
+    mono_font::{MonoTextStyle, ascii::FONT_6X10},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text},
+};
+
+use super::{Key, KeyEvent, Keypad};
+use crate::{
+    calendar::{SECONDS_PER_DAY, civil_from_days, days_from_civil, days_in_month},
+    soft_keys::{self, SoftKeys},
+};
+
+const CHARACTER_WIDTH: i32 = 6;
+const ROW_HEIGHT: i32 = 10;
+/// Where the body of a dialog starts, below its prompt.
+const BODY_TOP: i32 = 12;
+
+const YEARS: RangeInclusive<i32> = 1970..=2099;
+
+fn digit(key: Key) -> Option<i32> {
+    match key {
+        Key::Zero => Some(0),
+        Key::One => Some(1),
+        Key::Two => Some(2),
+        Key::Three => Some(3),
+        Key::Four => Some(4),
+        Key::Five => Some(5),
+        Key::Six => Some(6),
+        Key::Seven => Some(7),
+        Key::Eight => Some(8),
+        Key::Nine => Some(9),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Answer {
+    Accept,
+    Back,
+}
+
+fn soft_keys(accept: &str) -> SoftKeys<'_, Answer> {
+    SoftKeys::new()
+        .select(accept, Answer::Accept)
+        .cancel("Back", Answer::Back)
+}
+
+/// Rows of options that fit below the prompt and above the soft keys.
+fn rows(bounding_box: Rectangle) -> usize {
+    let height = soft_keys::above(bounding_box).size.height as i32;
+    ((height - BODY_TOP) / ROW_HEIGHT).max(1) as usize
+}
+
+/// Clears `draw_target` and draws `prompt` along the top of it and
+/// `soft_keys` along the bottom.
+fn draw_prompt<D>(prompt: &str, soft_keys: &SoftKeys<Answer>, draw_target: &mut D)
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let bounding_box = draw_target.bounding_box();
+    let _ = bounding_box
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(draw_target);
+    soft_keys.draw(draw_target);
+    let _ = Text::with_baseline(
+        prompt,
+        bounding_box.top_left + Point::new(2, 1),
+        MonoTextStyle::new(&FONT_6X10, BinaryColor::Off),
+        Baseline::Top,
+    )
+    .draw(draw_target);
+}
+
+/// Draws `text` at `position`, inverted if `focused`.
+fn draw_field<D>(text: &str, position: Point, focused: bool, draw_target: &mut D)
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let color = if focused {
+        let width = text.len() as u32 * CHARACTER_WIDTH as u32;
+        let _ = Rectangle::new(position, Size::new(width, ROW_HEIGHT as u32))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+            .draw(draw_target);
+        BinaryColor::On
+    } else {
+        BinaryColor::Off
+    };
+    let _ = Text::with_baseline(
+        text,
+        position,
+        MonoTextStyle::new(&FONT_6X10, color),
+        Baseline::Top,
+    )
+    .draw(draw_target);
+}
+
+/// Yes or No, with No selected to begin with.
+pub async fn confirm<KEYPAD, D>(
+    prompt: &str,
+    keypad: &mut KEYPAD,
+    draw_target: &mut D,
+) -> Option<bool>
+where
+    KEYPAD: Keypad,
+    D: DrawTarget<Color = BinaryColor>,
+{
+    choice(
+        prompt,
+        &[("Yes", true), ("No", false)],
+        1,
+        keypad,
+        draw_target,
+    )
+    .await
+}
+
+fn draw_choice<T, D>(
+    prompt: &str,
+    options: &[(&str, T)],
+    current: usize,
+    index: usize,
+    top: usize,
+    draw_target: &mut D,
+) where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    draw_prompt(prompt, &soft_keys("OK"), draw_target);
+    let bounding_box = draw_target.bounding_box();
+    let rows = rows(bounding_box);
+
+    for (row, (i, (label, _))) in options.iter().enumerate().skip(top).take(rows).enumerate() {
+        let row_top = bounding_box.top_left + Point::new(0, BODY_TOP + row as i32 * ROW_HEIGHT);
+        let color = if i == index {
+            let _ = Rectangle::new(
+                row_top,
+                Size::new(bounding_box.size.width, ROW_HEIGHT as u32),
+            )
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::Off))
+            .draw(draw_target);
+            BinaryColor::On
+        } else {
+            BinaryColor::Off
+        };
+
+        let radio = Circle::new(row_top + Point::new(2, 2), 6);
+        let style = if i == current {
+            PrimitiveStyle::with_fill(color)
+        } else {
+            PrimitiveStyle::with_stroke(color, 1)
+        };
+        let _ = radio.into_styled(style).draw(draw_target);
+        let _ = Text::with_baseline(
+            label,
+            row_top + Point::new(11, 0),
+            MonoTextStyle::new(&FONT_6X10, color),
+            Baseline::Top,
+        )
+        .draw(draw_target);
+    }
+}
+
+/// One of `options`, the one at `current` being marked as the present
+/// choice and selected to begin with.  Up and Down move the selection and
+/// digits jump to the numbered option.
+pub async fn choice<T, KEYPAD, D>(
+    prompt: &str,
+    options: &[(&str, T)],
+    current: usize,
+    keypad: &mut KEYPAD,
+    draw_target: &mut D,
+) -> Option<T>
+where
+    T: Copy,
+    KEYPAD: Keypad,
+    D: DrawTarget<Color = BinaryColor>,
+{
+    if options.is_empty() {
+        return None;
+    }
+    let rows = rows(draw_target.bounding_box());
+    let mut index = current.min(options.len() - 1);
+    let mut top = 0;
+
+    loop {
+        // keep the selection in view
+        top = top.clamp(index.saturating_sub(rows - 1), index);
+        draw_choice(prompt, options, current, index, top, draw_target);
+
+        let event = keypad.event().await;
+        match soft_keys("OK").resolve(event) {
+            Some(Answer::Accept) => return Some(options[index].1),
+            Some(Answer::Back) => return None,
+            None => {}
+        }
+        match event {
+            KeyEvent::Down(Key::Up) | KeyEvent::Repeat(Key::Up) => {
+                index = index.checked_sub(1).unwrap_or(options.len() - 1);
+            }
+            KeyEvent::Down(Key::Down) | KeyEvent::Repeat(Key::Down) => {
+                index = (index + 1) % options.len();
+            }
+            KeyEvent::Down(key) => {
+                if let Some(number) = digit(key)
+                    && (1..=options.len()).contains(&(number as usize))
+                {
+                    index = number as usize - 1;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A number in `range`, starting from `initial`.  Up and Down step it and
+/// digits type it in, anything over the range being brought back within it.
+pub async fn number<KEYPAD, D>(
+    prompt: &str,
+    range: RangeInclusive<i32>,
+    initial: i32,
+    keypad: &mut KEYPAD,
+    draw_target: &mut D,
+) -> Option<i32>
+where
+    KEYPAD: Keypad,
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let (min, max) = (*range.start(), *range.end());
+    let mut value = initial.clamp(min, max);
+    let mut typing = false;
+
+    let soft_keys = soft_keys("OK");
+
+    loop {
+        draw_prompt(prompt, &soft_keys, draw_target);
+        let bounding_box = draw_target.bounding_box();
+        let mut text: heapless::String<12> = heapless::String::new();
+        let _ = write!(text, "{}", value);
+        // arrows either side show which way there is still room to go
+        let centre =
+            bounding_box.top_left + Point::new(bounding_box.size.width as i32 / 2, BODY_TOP + 8);
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+        let _ = Text::with_alignment(&text, centre, style, Alignment::Center).draw(draw_target);
+        if value > min {
+            let _ = Text::with_alignment("<", centre - Point::new(30, 0), style, Alignment::Center)
+                .draw(draw_target);
+        }
+        if value < max {
+            let _ = Text::with_alignment(">", centre + Point::new(30, 0), style, Alignment::Center)
+                .draw(draw_target);
+        }
+
+        let event = keypad.event().await;
+        match soft_keys.resolve(event) {
+            Some(Answer::Accept) => return Some(value),
+            Some(Answer::Back) => return None,
+            None => {}
+        }
+        match event {
+            KeyEvent::Down(Key::Up) | KeyEvent::Repeat(Key::Up) => {
+                value = value.saturating_add(1).min(max);
+                typing = false;
+            }
+            KeyEvent::Down(Key::Down) | KeyEvent::Repeat(Key::Down) => {
+                value = value.saturating_sub(1).max(min);
+                typing = false;
+            }
+            KeyEvent::Down(key) => {
+                if let Some(digit) = digit(key) {
+                    let typed = if typing {
+                        value.saturating_mul(10).saturating_add(digit)
+                    } else {
+                        digit
+                    };
+                    value = typed.clamp(min, max);
+                    typing = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Day,
+    Month,
+    Year,
+    Hour,
+    Minute,
+}
+
+impl Field {
+    const ALL: [Field; 5] = [
+        Field::Day,
+        Field::Month,
+        Field::Year,
+        Field::Hour,
+        Field::Minute,
+    ];
+}
+
+struct DateTime {
+    year: i32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+}
+
+impl DateTime {
+    fn from_timestamp(timestamp: i64) -> Self {
+        let (year, month, day) = civil_from_days(timestamp.div_euclid(SECONDS_PER_DAY));
+        let seconds = timestamp.rem_euclid(SECONDS_PER_DAY) as u32;
+        let mut date_time = Self {
+            year,
+            month,
+            day,
+            hour: seconds / 3600,
+            minute: seconds / 60 % 60,
+        };
+        if !YEARS.contains(&year) {
+            date_time.year = *YEARS.start();
+            date_time.month = 1;
+            date_time.day = 1;
+        }
+        date_time
+    }
+
+    fn timestamp(&self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * SECONDS_PER_DAY
+            + i64::from(self.hour * 3600 + self.minute * 60)
+    }
+
+    /// Steps `field` by `delta`, wrapping around within its range.
+    fn step(&mut self, field: Field, delta: i32) {
+        fn wrap(value: u32, delta: i32, first: u32, count: u32) -> u32 {
+            ((value - first) as i32 + delta).rem_euclid(count as i32) as u32 + first
+        }
+
+        match field {
+            Field::Day => {
+                self.day = wrap(self.day, delta, 1, days_in_month(self.year, self.month));
+            }
+            Field::Month => self.month = wrap(self.month, delta, 1, 12),
+            Field::Year => {
+                let years = YEARS.end() - YEARS.start() + 1;
+                self.year = (self.year - YEARS.start() + delta).rem_euclid(years) + YEARS.start();
+            }
+            Field::Hour => self.hour = wrap(self.hour, delta, 0, 24),
+            Field::Minute => self.minute = wrap(self.minute, delta, 0, 60),
+        }
+        self.day = self.day.min(days_in_month(self.year, self.month));
+    }
+
+    fn draw<D>(&self, prompt: &str, focus: Field, soft_keys: &SoftKeys<Answer>, draw_target: &mut D)
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        draw_prompt(prompt, soft_keys, draw_target);
+        let top_left = draw_target.bounding_box().top_left;
+        let date = top_left + Point::new(2, BODY_TOP);
+        let time = date + Point::new(0, ROW_HEIGHT + 2);
+        let column = |n: i32| Point::new(n * CHARACTER_WIDTH, 0);
+
+        let mut text: heapless::String<4> = heapless::String::new();
+        let mut field = |value: &dyn core::fmt::Display, width: usize, position, field| {
+            text.clear();
+            let _ = write!(text, "{:0width$}", value, width = width);
+            draw_field(&text, position, focus == field, draw_target);
+        };
+        field(&self.day, 2, date, Field::Day);
+        field(&self.month, 2, date + column(3), Field::Month);
+        field(&self.year, 4, date + column(6), Field::Year);
+        field(&self.hour, 2, time, Field::Hour);
+        field(&self.minute, 2, time + column(3), Field::Minute);
+
+        let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+        for (separator, position) in [
+            (".", date + column(2)),
+            (".", date + column(5)),
+            (":", time + column(2)),
+        ] {
+            let _ =
+                Text::with_baseline(separator, position, style, Baseline::Top).draw(draw_target);
+        }
+    }
+}
+
+/// A date and time, starting from the Unix timestamp `initial` and returned
+/// as one, to the minute.  Up and Down change the highlighted field, Select
+/// moves on to the next one and accepts after the last, and Cancel goes
+/// back a field or, from the first, abandons the dialog.
+pub async fn date_time<KEYPAD, D>(
+    prompt: &str,
+    initial: i64,
+    keypad: &mut KEYPAD,
+    draw_target: &mut D,
+) -> Option<i64>
+where
+    KEYPAD: Keypad,
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let mut date_time = DateTime::from_timestamp(initial);
+    let mut focus = 0;
+
+    loop {
+        let last = focus == Field::ALL.len() - 1;
+        let soft_keys = soft_keys(if last { "OK" } else { "Next" });
+        date_time.draw(prompt, Field::ALL[focus], &soft_keys, draw_target);
+
+        let event = keypad.event().await;
+        match soft_keys.resolve(event) {
+            Some(Answer::Accept) if last => return Some(date_time.timestamp()),
+            Some(Answer::Accept) => focus += 1,
+            Some(Answer::Back) => focus = focus.checked_sub(1)?,
+            None => {}
+        }
+        match event {
+            KeyEvent::Down(Key::Up) | KeyEvent::Repeat(Key::Up) => {
+                date_time.step(Field::ALL[focus], 1);
+            }
+            KeyEvent::Down(Key::Down) | KeyEvent::Repeat(Key::Down) => {
+                date_time.step(Field::ALL[focus], -1);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fake;
+
+    fn keypad(keys: &[Key]) -> fake::Keypad {
+        let mut keypad = fake::Keypad::default();
+        for key in keys {
+            keypad.push(KeyEvent::Down(*key));
+        }
+        keypad
+    }
+
+    #[test]
+    fn test_choice() {
+        let mut display = fake::Display::default();
+        let options = [("Off", 0), ("Low", 1), ("High", 2)];
+
+        let chosen = embassy_futures::block_on(choice(
+            "Level",
+            &options,
+            1,
+            &mut keypad(&[Key::Down, Key::Down, Key::Select]),
+            &mut display,
+        ));
+        assert_eq!(chosen, Some(0));
+
+        let chosen = embassy_futures::block_on(choice(
+            "Level",
+            &options,
+            1,
+            &mut keypad(&[Key::Three, Key::Cancel]),
+            &mut display,
+        ));
+        assert_eq!(chosen, None);
+
+        let confirmed = embassy_futures::block_on(confirm(
+            "Sure?",
+            &mut keypad(&[Key::Up, Key::Select]),
+            &mut display,
+        ));
+        assert_eq!(confirmed, Some(true));
+    }
+
+    #[test]
+    fn test_number() {
+        let mut display = fake::Display::default();
+
+        let value = embassy_futures::block_on(number(
+            "Volume",
+            0..=10,
+            5,
+            &mut keypad(&[Key::Up, Key::Up, Key::Down, Key::Select]),
+            &mut display,
+        ));
+        assert_eq!(value, Some(6));
+
+        let value = embassy_futures::block_on(number(
+            "Volume",
+            0..=10,
+            5,
+            &mut keypad(&[Key::One, Key::Two, Key::Select]),
+            &mut display,
+        ));
+        assert_eq!(value, Some(10));
+    }
+
+    #[test]
+    fn test_date_time() {
+        let mut display = fake::Display::default();
+        // 2024-01-31 23:59:30
+        let initial = days_from_civil(2024, 1, 31) * SECONDS_PER_DAY + 23 * 3600 + 59 * 60 + 30;
+
+        // day stays, month up to February clamps the day, then the minute wraps
+        let keys = [
+            Key::Select,
+            Key::Up,
+            Key::Select,
+            Key::Select,
+            Key::Select,
+            Key::Up,
+            Key::Select,
+        ];
+        let chosen =
+            embassy_futures::block_on(date_time("Date", initial, &mut keypad(&keys), &mut display));
+        let expected = days_from_civil(2024, 2, 29) * SECONDS_PER_DAY + 23 * 3600;
+        assert_eq!(chosen, Some(expected));
+
+        let chosen = embassy_futures::block_on(date_time(
+            "Date",
+            initial,
+            &mut keypad(&[Key::Select, Key::Cancel, Key::Cancel]),
+            &mut display,
+        ));
+        assert_eq!(chosen, None);
+    }
+}
@@ -1,20 +1,48 @@
 use shared::Backlight;
 use web_sys::Element;
 
-pub struct Light(Element);
+/// Opacity of the glow at full brightness, as drawn in index.html.
+const OPACITY: f32 = 0.475;
+
+pub struct Light {
+    element: Element,
+    brightness: u8,
+    on: bool,
+}
 
 impl Light {
     pub fn new(element: Element) -> Self {
-        Self(element)
+        Self {
+            element,
+            brightness: 100,
+            on: false,
+        }
+    }
+
+    fn update(&mut self) {
+        let style = if self.on {
+            let opacity = OPACITY * f32::from(self.brightness.min(100)) / 100.0;
+            format!("color: lime; opacity: {}", opacity)
+        } else {
+            "color: black".into()
+        };
+        self.element.set_attribute("style", &style).unwrap();
     }
 }
 
 impl Backlight for Light {
     fn on(&mut self) {
-        self.0.set_attribute("style", "color: lime").unwrap();
+        self.on = true;
+        self.update();
     }
 
     fn off(&mut self) {
-        self.0.set_attribute("style", "color: black").unwrap();
+        self.on = false;
+        self.update();
+    }
+
+    fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+        self.update();
     }
 }
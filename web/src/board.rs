@@ -0,0 +1,38 @@
+use shared::{Peripherals, keypad::Gestures};
+
+use crate::{backlight, buzzer, display, keypad, power, rtc, storage, vibration_motor};
+
+pub struct Board {
+    pub vibration_motor: vibration_motor::Motor,
+    pub buzzer: buzzer::Buzzer,
+    pub display: display::Display,
+    pub keypad: Gestures<keypad::DomKeypad>,
+    pub rtc: rtc::Clock,
+    pub backlight: backlight::Light,
+    pub storage: storage::Local,
+    pub power: power::DomPower,
+}
+
+impl shared::Board for Board {
+    type VibrationMotor = vibration_motor::Motor;
+    type Buzzer = buzzer::Buzzer;
+    type Display = display::Display;
+    type Keypad = Gestures<keypad::DomKeypad>;
+    type Rtc = rtc::Clock;
+    type Backlight = backlight::Light;
+    type Storage = storage::Local;
+    type PowerButton = power::DomPower;
+
+    fn peripherals(&mut self) -> Peripherals<'_, Self> {
+        Peripherals {
+            vibration_motor: &mut self.vibration_motor,
+            buzzer: &mut self.buzzer,
+            display: &mut self.display,
+            keypad: &mut self.keypad,
+            rtc: &mut self.rtc,
+            backlight: &mut self.backlight,
+            storage: &mut self.storage,
+            power_button: &mut self.power,
+        }
+    }
+}
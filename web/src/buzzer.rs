@@ -1,13 +1,20 @@
 use std::{cell::RefCell, rc::Rc, sync::Mutex};
 
+use shared::audio::amplitude;
 use wasm_bindgen::prelude::*;
 use web_sys::{AudioContext, Element, GainNode, OscillatorNode, OscillatorType};
 
+/// The oscillator runs from the first click on the phone until the page
+/// closes, and muting only turns its gain down: a Web Audio oscillator cannot
+/// be started again once stopped, and changing the gain takes effect as
+/// quickly as the rp's PWM duty cycle.
 pub struct Buzzer {
     element: Element,
     oscillator: Rc<Mutex<Option<OscillatorNode>>>,
     gain: Rc<Mutex<Option<GainNode>>>,
     closure: RefCell<Option<Closure<dyn FnMut()>>>,
+    volume: u8,
+    muted: bool,
 }
 
 impl Buzzer {
@@ -17,6 +24,8 @@ impl Buzzer {
             oscillator: Rc::new(Mutex::new(None)),
             gain: Rc::new(Mutex::new(None)),
             closure: RefCell::new(None),
+            volume: 100,
+            muted: true,
         };
 
         let o = Rc::clone(&result.oscillator);
@@ -36,6 +45,9 @@ impl Buzzer {
                     oscillator.connect_with_audio_node(&gain).unwrap();
                     gain.connect_with_audio_node(&audio_context.destination())
                         .unwrap();
+                    // silent until the next unmute
+                    gain.gain().set_value(0.0);
+                    oscillator.start().unwrap();
                     ox.replace(oscillator);
                     g.lock().unwrap().replace(gain);
                 }
@@ -57,39 +69,31 @@ impl Buzzer {
 
         result
     }
+
+    fn update(&mut self) {
+        let binding = Rc::clone(&self.gain);
+        let mut gain = binding.lock().unwrap();
+        if let Some(g) = gain.as_mut() {
+            // on the same curve as the rp, so volume 0 is as quiet as muted
+            let level = if self.muted {
+                0.0
+            } else {
+                f32::from(amplitude(self.volume)) / 1000.0
+            };
+            g.gain().set_value(level);
+        }
+    }
 }
 
 impl shared::Buzzer for Buzzer {
     fn mute(&mut self) {
-        let binding = Rc::clone(&self.oscillator);
-        let mut oscillator = binding.lock().unwrap();
-        if let Some(o) = oscillator.as_mut() {
-            match o.stop() {
-                Ok(()) => {}
-                Err(e) => {
-                    let dom_exception: Option<&web_sys::DomException> = e.as_ref().dyn_ref();
-                    if dom_exception.unwrap().code() != web_sys::DomException::INVALID_STATE_ERR {
-                        // panic!("HEY");
-                    }
-                }
-            }
-        }
+        self.muted = true;
+        self.update();
     }
 
     fn unmute(&mut self) {
-        let binding = Rc::clone(&self.oscillator);
-        let mut oscillator = binding.lock().unwrap();
-        if let Some(o) = oscillator.as_mut() {
-            match o.start() {
-                Ok(()) => {}
-                Err(e) => {
-                    let dom_exception: Option<&web_sys::DomException> = e.as_ref().dyn_ref();
-                    if dom_exception.unwrap().code() != web_sys::DomException::INVALID_STATE_ERR {
-                        // panic!("HEY");
-                    }
-                }
-            }
-        }
+        self.muted = false;
+        self.update();
     }
 
     fn set_frequency(&mut self, frequency: u16) {
@@ -101,10 +105,7 @@ impl shared::Buzzer for Buzzer {
     }
 
     fn set_volume(&mut self, volume: u8) {
-        let binding = Rc::clone(&self.gain);
-        let mut gain = binding.lock().unwrap();
-        if let Some(g) = gain.as_mut() {
-            g.gain().set_value(volume as f32 / 100.0);
-        }
+        self.volume = volume;
+        self.update();
     }
 }
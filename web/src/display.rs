@@ -9,7 +9,10 @@ use embedded_graphics_web_simulator::{
 };
 use web_sys::Element;
 
-pub struct Display(WebSimulatorDisplay<embedded_graphics::pixelcolor::BinaryColor>);
+pub struct Display(
+    WebSimulatorDisplay<embedded_graphics::pixelcolor::BinaryColor>,
+    Element,
+);
 
 impl Display {
     pub fn new(element: Element) -> Self {
@@ -22,7 +25,7 @@ impl Display {
         let display: WebSimulatorDisplay<embedded_graphics::pixelcolor::BinaryColor> =
             WebSimulatorDisplay::new((84, 48), &output_settings, Some(&element));
 
-        Self(display)
+        Self(display, element)
     }
 }
 
@@ -41,6 +44,15 @@ impl DrawTarget for Display {
     }
 }
 
+impl shared::Display for Display {
+    fn set_contrast(&mut self, contrast: u8) {
+        // 50% looks like the plain canvas, and 0% is still readable rather
+        // than flat grey
+        let style = format!("filter: contrast({}%)", 50 + u32::from(contrast.min(100)));
+        self.1.set_attribute("style", &style).unwrap();
+    }
+}
+
 impl Dimensions for Display {
     fn bounding_box(&self) -> Rectangle {
         self.0.bounding_box()
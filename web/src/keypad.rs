@@ -2,7 +2,7 @@ use core::cell::RefCell;
 use std::rc::Rc;
 
 use embassy_time::Timer;
-use shared::{Key, Keypad};
+use shared::{Key, KeyEvent, Keypad};
 
 use super::DomB;
 
@@ -68,42 +68,40 @@ impl DomKeypad {
 
 unsafe impl Send for DomKeypad {}
 
+impl DomKeypad {
+    fn buttons(&self) -> [(&Rc<RefCell<DomB>>, Key); 16] {
+        [
+            (&self.cancel, Key::Cancel),
+            (&self.select, Key::Select),
+            (&self.up, Key::Up),
+            (&self.down, Key::Down),
+            (&self.one, Key::One),
+            (&self.two, Key::Two),
+            (&self.three, Key::Three),
+            (&self.four, Key::Four),
+            (&self.five, Key::Five),
+            (&self.six, Key::Six),
+            (&self.seven, Key::Seven),
+            (&self.eight, Key::Eight),
+            (&self.nine, Key::Nine),
+            (&self.asterisk, Key::Asterisk),
+            (&self.zero, Key::Zero),
+            (&self.hash, Key::Hash),
+        ]
+    }
+}
+
 impl Keypad for DomKeypad {
-    async fn event(&mut self) -> shared::KeyEvent {
+    async fn event(&mut self) -> KeyEvent {
         loop {
             Timer::after_millis(30).await;
-            if (*self.cancel).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Cancel);
-            } else if (*self.select).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Select);
-            } else if (*self.up).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Up);
-            } else if (*self.down).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Down);
-            } else if (*self.one).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::One);
-            } else if (*self.two).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Two);
-            } else if (*self.three).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Three);
-            } else if (*self.four).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Four);
-            } else if (*self.five).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Five);
-            } else if (*self.six).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Six);
-            } else if (*self.seven).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Seven);
-            } else if (*self.eight).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Eight);
-            } else if (*self.nine).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Nine);
-            } else if (*self.asterisk).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Asterisk);
-            } else if (*self.zero).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Zero);
-            } else if (*self.hash).borrow_mut().check() {
-                return shared::KeyEvent::Down(Key::Hash);
+            for (button, key) in self.buttons() {
+                let mut button = (**button).borrow_mut();
+                if button.check() {
+                    return KeyEvent::Down(key);
+                } else if button.check_released() {
+                    return KeyEvent::Up(key);
+                }
             }
         }
     }
@@ -1,13 +1,18 @@
 #![allow(unexpected_cfgs)]
 mod backlight;
+mod board;
 mod buzzer;
 mod display;
 mod keypad;
 mod power;
 mod rtc;
+mod storage;
+mod system;
 mod vibration_motor;
 
+use applications::Applications;
 use embassy_executor::Spawner;
+use shared::{keypad::Gestures, registry::Launcher};
 
 #[embassy_executor::main]
 async fn main(_spawner: Spawner) {
@@ -16,63 +21,40 @@ async fn main(_spawner: Spawner) {
     let window = web_sys::window().expect("no global `window` exists");
     let document = window.document().expect("should have a document on window");
     let svg = document.get_element_by_id("svg1").unwrap();
-    let mut vibration_motor = vibration_motor::Motor::new(svg);
+    let vibration_motor = vibration_motor::Motor::new(svg);
 
     let svg = document.get_element_by_id("nokia").unwrap();
-    let mut buzzer = buzzer::Buzzer::new(svg);
-    let mut rtc = rtc::Clock::new();
+    let buzzer = buzzer::Buzzer::new(svg);
+    let rtc = rtc::Clock::new();
 
     let svg = document.get_element_by_id("display").unwrap();
-    let mut display = display::Display::new(svg);
+    let display = display::Display::new(svg);
 
     let svg = document.get_element_by_id("backlight").unwrap();
-    let mut light = backlight::Light::new(svg);
+    let backlight = backlight::Light::new(svg);
 
-    let mut keypad = keypad::DomKeypad::new(
+    let keypad = Gestures::new(keypad::DomKeypad::new(
         "cancel", "select", "up", "down", "one", "two", "three", "four", "five", "six", "seven",
         "eight", "nine", "asterisk", "zero", "hash",
-    );
-
-    let mut power = power::DomPower::new("power");
-
-    let items = ["Clock", "Hardware Test"];
-    let mut menu = shared::menu::Menu::new(&items);
-    loop {
-        let i = loop {
-            if let Some(index) = menu.process(&mut keypad, &mut display).await {
-                break index;
-            }
-        };
-        if i == 0 {
-            let clock = clock::Clock;
-            shared::run_app(
-                clock,
-                &mut vibration_motor,
-                &mut buzzer,
-                &mut display,
-                &mut keypad,
-                &mut rtc,
-                &mut light,
-                &mut power,
-                None,
-            )
-            .await;
-        } else {
-            let hardware_test = hardware_test::HardwareTest::default();
-            shared::run_app(
-                hardware_test,
-                &mut vibration_motor,
-                &mut buzzer,
-                &mut display,
-                &mut keypad,
-                &mut rtc,
-                &mut light,
-                &mut power,
-                None,
-            )
-            .await;
-        }
-    }
+    ));
+
+    let power = power::DomPower::new("power");
+    let storage = storage::Local::new();
+
+    let mut board = board::Board {
+        vibration_motor,
+        buzzer,
+        display,
+        keypad,
+        rtc,
+        backlight,
+        storage,
+        power,
+    };
+
+    Launcher::<Applications>::new()
+        .run(&mut board, &mut system::System::default())
+        .await;
 }
 
 use core::cell::RefCell;
@@ -82,6 +64,8 @@ use wasm_bindgen::{JsCast, closure::Closure};
 
 struct DomB {
     was_clicked: bool,
+    pressed: bool,
+    was_released: bool,
 }
 
 impl DomB {
@@ -89,22 +73,41 @@ impl DomB {
     fn new(id: &'static str) -> Rc<RefCell<Self>> {
         let window = web_sys::window().expect("no global `window` exists");
         let document = window.document().expect("should have a document on window");
-        let s = Self { was_clicked: false };
+        let s = Self {
+            was_clicked: false,
+            pressed: false,
+            was_released: false,
+        };
         let r = Rc::new(RefCell::new(s));
-        let g = r.clone();
+        let element = document.get_element_by_id(id).unwrap();
 
+        let g = r.clone();
         let closure = Closure::<dyn FnMut(_)>::new(move |_event: web_sys::MouseEvent| {
-            (*g).borrow_mut().was_clicked = true;
+            let mut b = (*g).borrow_mut();
+            b.was_clicked = true;
+            b.pressed = true;
         });
-
-        document
-            .get_element_by_id(id)
-            .unwrap()
+        element
             .add_event_listener_with_callback("mousedown", closure.as_ref().unchecked_ref())
             .unwrap();
-
         closure.forget();
 
+        // dragging off the button lets go of it too
+        for event in ["mouseup", "mouseleave"] {
+            let g = r.clone();
+            let closure = Closure::<dyn FnMut(_)>::new(move |_event: web_sys::MouseEvent| {
+                let mut b = (*g).borrow_mut();
+                if b.pressed {
+                    b.pressed = false;
+                    b.was_released = true;
+                }
+            });
+            element
+                .add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+                .unwrap();
+            closure.forget();
+        }
+
         r
     }
 
@@ -113,4 +116,10 @@ impl DomB {
         self.was_clicked = false;
         result
     }
+
+    fn check_released(&mut self) -> bool {
+        let result = self.was_released;
+        self.was_released = false;
+        result
+    }
 }
@@ -0,0 +1,64 @@
+use core::fmt::Write;
+
+use shared::storage::{Error, MAX_VALUE, check_key};
+
+/// Keeps records in the browser's localStorage, as hex strings under the
+/// key with [`PREFIX`] in front.
+pub struct Local(web_sys::Storage);
+
+/// Keeps these records apart from anything else on the same origin.
+const PREFIX: &str = "nokia.";
+
+fn item(key: &str) -> Result<String, Error> {
+    check_key(key)?;
+    Ok(format!("{}{}", PREFIX, key))
+}
+
+impl Local {
+    pub fn new() -> Self {
+        let window = web_sys::window().expect("no global `window` exists");
+        Self(
+            window
+                .local_storage()
+                .unwrap()
+                .expect("should have localStorage on window"),
+        )
+    }
+}
+
+impl shared::Storage for Local {
+    async fn read(&mut self, key: &str, buffer: &mut [u8]) -> Result<Option<usize>, Error> {
+        let Some(hex) = self.0.get_item(&item(key)?).map_err(|_| Error::Backend)? else {
+            return Ok(None);
+        };
+        if hex.len() % 2 != 0 {
+            return Err(Error::Corrupt);
+        }
+
+        let length = hex.len() / 2;
+        let buffer = buffer.get_mut(..length).ok_or(Error::TooLarge)?;
+        for (byte, pair) in buffer.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            let pair = core::str::from_utf8(pair).map_err(|_| Error::Corrupt)?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| Error::Corrupt)?;
+        }
+        Ok(Some(length))
+    }
+
+    async fn write(&mut self, key: &str, value: &[u8]) -> Result<(), Error> {
+        let item = item(key)?;
+        if value.len() > MAX_VALUE {
+            return Err(Error::TooLarge);
+        }
+
+        let mut hex = String::with_capacity(value.len() * 2);
+        for byte in value {
+            let _ = write!(hex, "{:02x}", byte);
+        }
+        // fails with a QuotaExceededError once the origin's quota is used up
+        self.0.set_item(&item, &hex).map_err(|_| Error::Full)
+    }
+
+    async fn delete(&mut self, key: &str) -> Result<(), Error> {
+        self.0.remove_item(&item(key)?).map_err(|_| Error::Backend)
+    }
+}
@@ -0,0 +1,34 @@
+use shared::{
+    RequestError, UsbRx, UsbTx, backlight::Timeout, keyguard::Keyguard, settings::Settings,
+};
+
+#[derive(Default)]
+pub struct System {
+    settings: Settings,
+}
+
+static KEYGUARD: Keyguard = Keyguard::new();
+static BACKLIGHT: Timeout = Timeout::new();
+
+impl shared::system::System for System {
+    fn keyguard(&self) -> &Keyguard {
+        &KEYGUARD
+    }
+
+    fn backlight(&self) -> &Timeout {
+        &BACKLIGHT
+    }
+
+    fn settings(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    async fn usb_tx(&mut self, tx: UsbTx) -> Result<(), RequestError> {
+        log::info!("no USB in the simulator, dropping {:?}", tx);
+        Err(RequestError::Unsupported)
+    }
+
+    fn usb_rx(&mut self) -> Option<UsbRx> {
+        None
+    }
+}
@@ -1,5 +1,6 @@
 use shared::VibrationMotor;
-use web_sys::Element;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, SvgElement};
 
 pub struct Motor(Element);
 
@@ -17,4 +18,14 @@ impl VibrationMotor for Motor {
     fn stop(&mut self) {
         self.0.class_list().remove_1("vibrating").unwrap();
     }
+
+    fn set_intensity(&mut self, intensity: u8) {
+        // scales the shake animation in index.html
+        let shake = f32::from(intensity.min(100)) / 100.0;
+        self.0
+            .unchecked_ref::<SvgElement>()
+            .style()
+            .set_property("--shake", &shake.to_string())
+            .unwrap();
+    }
 }